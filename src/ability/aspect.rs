@@ -58,10 +58,14 @@ const MAX_VAL_SCHOOL:       usize = 7;
 /// Enumeration of the aesthetics (coolness) of an ability
 #[derive(
     Debug,
+    Default,
     Copy, Clone,
+    Eq, PartialEq,
+    Hash,
     Serialize, Deserialize
 )]
 pub enum Aesthetics {
+    #[default]
     Unset       = 0,
     Beautiful   = 1,
     Impressive  = 2,
@@ -73,10 +77,14 @@ pub enum Aesthetics {
 /// Enumeration of method by which an ability is performed
 #[derive(
     Debug,
+    Default,
     Copy, Clone,
+    Eq, PartialEq,
+    Hash,
     Serialize, Deserialize
 )]
 pub enum Method {
+    #[default]
     Unset       = 0,
     Staff       = 1,
     Wand        = 2,
@@ -87,10 +95,14 @@ pub enum Method {
 /// Enumeration of morality aspect of an ability
 #[derive(
     Debug,
+    Default,
     Copy, Clone,
+    Eq, PartialEq,
+    Hash,
     Serialize, Deserialize
 )]
 pub enum Morality {
+    #[default]
     Unset       = 0,
     Good        = 1,
     Neutral     = 2,
@@ -100,10 +112,14 @@ pub enum Morality {
 /// Enumeration of all schools of an ability
 #[derive(
     Debug,
+    Default,
     Copy, Clone,
+    Eq, PartialEq,
+    Hash,
     Serialize, Deserialize
 )]
 pub enum School {
+    #[default]
     Unset       = 0,
     Destruction = 1,
     Restoration = 2,
@@ -116,9 +132,11 @@ pub enum School {
 
 /// Structure containing all aspect classifications
 #[derive(
+    Copy, Clone,
     Default,
     Serialize, Deserialize
 )]
+#[serde(default)]
 pub struct Aspects {
     pub aesthetics: Aesthetics,
     pub element:    Element,
@@ -157,11 +175,6 @@ impl Aspects {
 /*  *  *  *  *  *  *  *\
  *     Aesthetics     *
 \*  *  *  *  *  *  *  */
-impl Default for Aesthetics {
-    fn default() -> Self {
-        Aesthetics::Unset
-    }
-}
 impl From<usize> for Aesthetics {
     fn from(src: usize) -> Self {
         match src {
@@ -184,11 +197,6 @@ impl Randomizable for Aesthetics {
 /*  *  *  *  *  *  *  *\
  *       Method       *
 \*  *  *  *  *  *  *  */
-impl Default for Method {
-    fn default() -> Self {
-        Method::Unset
-    }
-}
 impl From<usize> for Method {
     fn from(src: usize) -> Self {
         match src {
@@ -210,11 +218,6 @@ impl Randomizable for Method {
 /*  *  *  *  *  *  *  *\
  *      Morality      *
 \*  *  *  *  *  *  *  */
-impl Default for Morality {
-    fn default() -> Self {
-        Morality::Unset
-    }
-}
 impl From<usize> for Morality {
     fn from(src: usize) -> Self {
         match src {
@@ -235,11 +238,6 @@ impl Randomizable for Morality {
 /*  *  *  *  *  *  *  *\
  *       School       *
 \*  *  *  *  *  *  *  */
-impl Default for School {
-    fn default() -> Self {
-        School::Unset
-    }
-}
 impl From<usize> for School {
     fn from(src: usize) -> Self {
         match src {
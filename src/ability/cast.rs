@@ -0,0 +1,1002 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ability/cast.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the execution pipeline through which an actor casts
+    an ability at a target.
+
+    Execution proceeds in stages:
+        1. Validation  - caster, ability, and target exist; ability is ready;
+                         caster carries any reagents required; target is
+                         within range and line of sight; caster can bear
+                         the fatigue.
+        2. Resolution  - the set of affected positions and resulting effects
+                         are computed without mutating the world.
+        3. Application - effects are applied to the world, the caster is
+                         fatigued, and any empowering resources are drawn down.
+
+    A structured Outcome is returned for consumption by the UI.
+
+    Resources whose radius covers the caster empower abilities of their own
+    element, boosting potency according to their state. Each resource drawn
+    upon in this way drains its network by one state, and the Outcome reports
+    the resources actually drained.
+
+    Abilities requiring reagents cannot be cast unless the caster carries the
+    essences required, which are spent by the cast. Abilities may also infuse
+    essence of their own element from the caster's inventory to empower them.
+
+    Effects are resolved as soon as the cast is executed, and the caster pays
+    its costs at once. Abilities with no cast time take effect immediately;
+    otherwise the cast is queued on the world and its effects are applied
+    once the cast time has elapsed, unless the caster is interrupted first.
+    Costs paid for an interrupted cast are not refunded. Any channel duration
+    of the ability then occupies the caster until it elapses.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    error::Error,
+    fmt,
+};
+
+use crate::{
+    ability::{
+        Ability,
+        modifier::{
+            Circumstances,
+            Resolution,
+            Rule,
+        },
+        timing::Readiness,
+    },
+    actor::{
+        Actor,
+        combo::{
+            Bonus,
+            Combo,
+            Usage,
+        },
+        faction::{
+            self,
+            TargetingError,
+        },
+        fatigue::{
+            self,
+            Exhaustion,
+            FatigueError,
+        },
+        inventory::{
+            self,
+            Essence,
+            InventoryError,
+        },
+        status::{
+            self,
+            StatusEffect,
+        },
+    },
+    coords,
+    element::Element,
+    hex_directions,
+    mechanics::{
+        obstacle,
+        reaction::{
+            self,
+            Mutation,
+        },
+        resource::Resource,
+    },
+    world::World,
+    Plottable,
+};
+
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Maximum range (in cells) of any ability cast while blinded
+const BLINDED_RANGE: usize = 1;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the things an ability may be cast at
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Actor([u8; 16]),
+    Cell(coords::Position),
+    Area(Vec<coords::Position>),
+}
+
+/// Enumeration of the kinds of effect an ability may have on an actor
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EffectKind {
+    Damage,
+    Heal,
+    Afflict(status::Kind, f64),     // Status inflicted, and its duration
+}
+
+/// A single effect computed during resolution, to be applied to an actor
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Effect {
+    pub source:     [u8; 16],
+    pub target:     [u8; 16],
+    pub element:    Element,
+    pub kind:       EffectKind,
+    pub magnitude:  usize,
+}
+
+/// Report of an effect as it was actually applied to the world
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EffectReport {
+    pub target:     [u8; 16],
+    pub kind:       EffectKind,
+    pub magnitude:  usize,
+    pub defeated:   bool,
+}
+
+/// Structured report of a cast
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub caster:         [u8; 16],
+    pub ability:        [u8; 16],
+    pub positions:      Vec<coords::Position>,
+    pub effects:        Vec<EffectReport>,
+    pub mutations:      Vec<Mutation>,
+    pub combos:         Vec<String>,    // Names of the combos completed by the cast
+    pub resources:      Vec<[u8; 16]>,  // Resources drained to empower the cast, across their networks
+    pub essences:       Vec<Essence>,   // Essences spent from the caster's inventory
+    pub detected:       bool,
+    pub fatigue_cost:   usize,
+    pub exhaustion:     Exhaustion,
+    pub pending:        bool,           // Whether the effects await the end of the cast time
+}
+
+/// A cast whose effects await the end of its cast time
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingCast {
+    started:        f64,        // Tick at which the cast began
+    completes_at:   f64,        // Tick at which the cast takes effect
+    effects:        Vec<Effect>,
+    drawn:          Vec<[u8; 16]>,  // Resources drawn upon to empower the cast
+    outcome:        Outcome,    // Outcome of the cast, less the effects yet to be applied
+}
+
+/// A cast validated and resolved against the world, awaiting the caster's commitment to it
+#[derive(Debug)]
+struct Resolved {
+    positions:      Vec<coords::Position>,
+    effects:        Vec<Effect>,
+    mutations:      Vec<Mutation>,
+    detected:       bool,
+    fatigue_cost:   usize,
+    usage:          Usage,
+    combos:         Vec<Combo>,     // Combos completed by the cast
+    drawn:          Vec<[u8; 16]>,  // Resources drawn upon to empower the cast
+    essences:       Vec<Essence>,   // Essences to be spent from the caster's inventory
+}
+
+#[derive(Debug)]
+pub enum CastError {
+    UnknownCaster([u8; 16]),
+    UnknownAbility([u8; 16]),
+    UnknownTarget([u8; 16]),
+    Incapacitated,
+    NotReady(Readiness),
+    Blocked(Rule),
+    OutOfRange(coords::Position, u32, usize),
+    NoLineOfSight(coords::Position),
+    Targeting(TargetingError),
+    Fatigue(FatigueError),
+    Inventory(InventoryError),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl PendingCast {
+    pub fn caster(&self) -> &[u8; 16] {
+        &self.outcome.caster
+    }
+
+    pub fn ability(&self) -> &[u8; 16] {
+        &self.outcome.ability
+    }
+
+    pub fn started(&self) -> f64 {
+        self.started
+    }
+
+    pub fn completes_at(&self) -> f64 {
+        self.completes_at
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Executes the full casting pipeline for the given caster, ability, and target
+pub fn execute(world:       &mut World,
+               caster_uid:  &[u8; 16],
+               ability_uid: &[u8; 16],
+               target:      &Target) -> Result<Outcome, CastError> {
+    // Validate and resolve without mutating the world
+    let Resolved {positions, effects, mutations, detected, fatigue_cost, usage, combos, drawn, essences} =
+        prepare(world, caster_uid, ability_uid, target)?;
+
+    // Occupy and fatigue the caster
+    let tick = world.tick();
+    let caster = world.actor_mut(caster_uid).ok_or(CastError::UnknownCaster(*caster_uid))?;
+    caster.begin_cast(ability_uid, tick).map_err(|readiness| match readiness {
+        Some(readiness) => CastError::NotReady(readiness),
+        None            => CastError::UnknownAbility(*ability_uid),
+    })?;
+    let exhaustion = caster.exert(fatigue_cost)?;
+    caster.record_usage(usage, !combos.is_empty());
+    caster.inventory_mut().spend(&essences)?;
+
+    let cast_time = caster.ability(ability_uid).map_or(0.0, |ability| ability.timing().cast_time);
+
+    let outcome = Outcome {
+        caster:     *caster_uid,
+        ability:    *ability_uid,
+        positions,
+        effects:    Vec::new(),
+        mutations,
+        combos:     combos.into_iter().map(|combo| combo.name).collect(),
+        resources:  Vec::new(),
+        essences,
+        detected,
+        fatigue_cost,
+        exhaustion,
+        pending:    false,
+    };
+
+    // Abilities with a cast time take effect once it has elapsed
+    if cast_time > 0.0 {
+        world.queue_cast(PendingCast {
+            started:        tick,
+            completes_at:   tick + cast_time,
+            effects,
+            drawn,
+            outcome:        outcome.clone(),
+        });
+        return Ok(Outcome {pending: true, ..outcome});
+    }
+
+    Ok(take_effect(world, outcome, &effects, &drawn))
+}
+
+/// Validates and resolves the cast for the given caster, ability, and target without mutating the world
+fn prepare(world:       &World,
+           caster_uid:  &[u8; 16],
+           ability_uid: &[u8; 16],
+           target:      &Target) -> Result<Resolved, CastError> {
+    let caster = world.actor(caster_uid).ok_or(CastError::UnknownCaster(*caster_uid))?;
+    let ability = caster.ability(ability_uid).ok_or(CastError::UnknownAbility(*ability_uid))?;
+
+    let (positions, facing) = validate(world, caster, ability, target)?;
+    let fatigue_cost = fatigue::cost(ability);
+    caster.can_exert(fatigue_cost)?;
+
+    // Apply aspect rules before computing effects
+    let mut resolution = world.aspect_rules().resolve(ability.aspects(), &Circumstances::from(caster));
+    if let Some(rule) = resolution.blocked_by {
+        return Err(CastError::Blocked(rule));
+    }
+
+    // Check for combos completed by this cast, and boost its potency accordingly
+    let usage = Usage::new(ability, world.tick(), caster.combo_tracker().turn());
+    let combos: Vec<Combo> = world.combos().completed_by(caster.combo_tracker(), &usage)
+        .into_iter()
+        .cloned()
+        .collect();
+    for bonus in combos.iter().flat_map(|combo| combo.bonuses.iter()) {
+        if let Bonus::Potency(factor) = bonus {
+            resolution.potency_multiplier *= factor;
+        }
+    }
+
+    // Draw upon resources of the ability's element surrounding the caster
+    let empowering: Vec<&Resource> = world.resources_covering(caster.origin()).into_iter()
+        .filter(|resource| resource.empowers(*ability.element()))
+        .collect();
+    for resource in empowering.iter() {
+        resolution.potency_multiplier *= resource.potency_multiplier();
+    }
+    let drawn: Vec<[u8; 16]> = empowering.iter().map(|resource| *resource.uid()).collect();
+
+    // Infuse whatever essence of the ability's element remains once reagents are set aside
+    let mut essences = ability.reagents().clone();
+    let element = *ability.element();
+    if element != Element::Unset {
+        let reserved: u32 = essences.iter()
+            .filter(|reagent| reagent.element == element)
+            .map(|reagent| reagent.amount)
+            .sum();
+        let infused = u32::min(ability.infusion(), caster.inventory().amount(element) - reserved);
+        if infused > 0 {
+            resolution.potency_multiplier *= inventory::potency_multiplier(infused);
+            essences.push(Essence::new(element, infused));
+        }
+    }
+
+    let mut effects = resolve(world, caster, ability, &resolution, &positions);
+    let bonus_effects = resolve_combos(caster, ability, &combos, &effects);
+    effects.extend(bonus_effects);
+    let mut mutations = reaction::evaluate(world, *ability.element(), &positions, facing);
+
+    // Damaging abilities also strike the obstacles in their path, sparing cells already removed
+    if resolution.kind == EffectKind::Damage {
+        let magnitude = ability.potency() as f64 * caster.exhaustion().potency_modifier() * resolution.potency_multiplier;
+        let removed: Vec<coords::Position> = mutations.iter()
+            .filter_map(|mutation| match mutation {
+                Mutation::RemoveObstacleCells(_, cells) => Some(cells.clone()),
+                _                                       => None,
+            })
+            .flatten()
+            .collect();
+        let struck: Vec<coords::Position> = positions.iter()
+            .filter(|pos| !removed.contains(pos))
+            .cloned()
+            .collect();
+        mutations.extend(obstacle::evaluate_damage(world, *ability.element(), &struck, magnitude as usize));
+    }
+
+    Ok(Resolved {
+        positions,
+        effects,
+        mutations,
+        detected:   resolution.detected,
+        fatigue_cost,
+        usage,
+        combos,
+        drawn,
+        essences,
+    })
+}
+
+/// Completes the given pending cast, applying its effects to the world.
+/// Returns None without applying anything if the caster was interrupted, or is no longer present.
+pub fn complete(world: &mut World, pending: PendingCast) -> Option<Outcome> {
+    let cast = world.actor(pending.caster())?.active_cast()?;
+    if cast.ability != *pending.ability() || cast.started != pending.started {
+        return None;
+    }
+
+    Some(take_effect(world, pending.outcome, &pending.effects, &pending.drawn))
+}
+
+/// Draws upon the given resources, and applies the given effects and the mutations of the given outcome.
+/// Each drain may fall upon any member of the drawn resource's network, so the outcome reports the
+/// resources actually drained.
+fn take_effect(world: &mut World, mut outcome: Outcome, effects: &[Effect], drawn: &[[u8; 16]]) -> Outcome {
+    outcome.resources = drawn.iter()
+        .filter_map(|uid| world.consume_resource(uid))
+        .map(|(drained, _magnitude)| drained)
+        .collect();
+
+    // Apply the resolved effects and any elemental reactions
+    outcome.effects = apply(world, effects);
+    for mutation in outcome.mutations.iter() {
+        world.apply_mutation(mutation);
+    }
+
+    outcome
+}
+
+/// Validates the cast, returning the positions affected and the direction the caster faces on success.
+/// Actor and Cell targets are expanded by the ability's shape, while Area targets are taken as-is.
+pub fn validate(world:      &World,
+                caster:     &Actor,
+                ability:    &Ability,
+                target:     &Target) -> Result<(Vec<coords::Position>, hex_directions::Side), CastError> {
+    if !caster.can_act() {
+        return Err(CastError::Incapacitated);
+    }
+
+    match caster.readiness(ability.uid(), world.tick()) {
+        Some(Readiness::Ready)  => {},
+        Some(readiness)         => return Err(CastError::NotReady(readiness)),
+        None                    => return Err(CastError::UnknownAbility(*ability.uid())),
+    }
+
+    caster.inventory().can_spend(ability.reagents())?;
+
+    let aim_points = match target {
+        Target::Actor(uid)  => {
+            let target_actor = world.actor(uid).ok_or(CastError::UnknownTarget(*uid))?;
+            faction::can_target(world, caster.uid(), ability, uid)?;
+            vec![*target_actor.origin()]
+        },
+        Target::Cell(pos)   => vec![*pos],
+        Target::Area(area)  => area.clone(),
+    };
+
+    // Blinded casters can only target what is right in front of them
+    let range = if caster.has_status(status::Kind::Blinded) {
+        usize::min(ability.range(), BLINDED_RANGE)
+    } else {
+        ability.range()
+    };
+
+    let origin = caster.origin();
+    for pos in aim_points.iter() {
+        let distance = origin.distance_to(pos);
+        if distance as usize > range {
+            return Err(CastError::OutOfRange(*pos, distance, range));
+        }
+
+        if !world.has_line_of_sight(origin, pos) {
+            return Err(CastError::NoLineOfSight(*pos));
+        }
+    }
+
+    // Face the caster towards the (first) target
+    let facing = match aim_points.first() {
+        Some(aim_point) => hex_directions::Side::from(origin.delta_to(aim_point).bearing()),
+        None            => hex_directions::Side::default(),
+    };
+
+    match target {
+        Target::Area(_) => Ok((aim_points, facing)),
+        _               => {
+            // Expand the ability's shape from the target
+            let candidates: Vec<coords::Position> = world.actors().iter()
+                .filter(|actor| actor.uid() != caster.uid())
+                .map(|actor| *actor.origin())
+                .collect();
+
+            Ok((ability.shape().resolve(origin, &aim_points[0], facing, &candidates, world.ctx()), facing))
+        }
+    }
+}
+
+/// Computes the effects of the cast on the given positions without mutating the world
+pub fn resolve(world:       &World,
+               caster:      &Actor,
+               ability:     &Ability,
+               resolution:  &Resolution,
+               positions:   &[coords::Position]) -> Vec<Effect> {
+    let multiplier = caster.exhaustion().potency_modifier() * resolution.potency_multiplier;
+    let magnitude = (ability.potency() as f64 * multiplier) as usize;
+
+    let mut effects = Vec::new();
+    // Actors the ability may not be cast on are spared
+    let affected = world.actors().iter()
+        .filter(|actor| positions.contains(actor.origin()))
+        .filter(|actor| faction::can_target(world, caster.uid(), ability, actor.uid()).is_ok());
+
+    for actor in affected {
+        effects.push(Effect {
+            source:     *caster.uid(),
+            target:     *actor.uid(),
+            element:    *ability.element(),
+            kind:       resolution.kind,
+            magnitude,
+        });
+
+        for affliction in ability.afflictions() {
+            effects.push(Effect {
+                source:     *caster.uid(),
+                target:     *actor.uid(),
+                element:    *ability.element(),
+                kind:       EffectKind::Afflict(affliction.kind, affliction.duration),
+                magnitude:  (affliction.magnitude * multiplier) as usize,
+            });
+        }
+    }
+
+    effects
+}
+
+/// Computes the bonus effects granted by the given completed combos. Bonuses land upon every actor
+/// affected by the ability's own effects, save for healing, which is granted to the caster.
+pub fn resolve_combos(caster:   &Actor,
+                      ability:  &Ability,
+                      combos:   &[Combo],
+                      effects:  &[Effect]) -> Vec<Effect> {
+    let mut targets: Vec<[u8; 16]> = Vec::new();
+    for effect in effects {
+        if !targets.contains(&effect.target) {
+            targets.push(effect.target);
+        }
+    }
+
+    let mut bonus_effects = Vec::new();
+    let effect = |target: [u8; 16], kind: EffectKind, magnitude: usize| Effect {
+        source:     *caster.uid(),
+        target,
+        element:    *ability.element(),
+        kind,
+        magnitude,
+    };
+
+    for bonus in combos.iter().flat_map(|combo| combo.bonuses.iter()) {
+        match *bonus {
+            Bonus::Potency(_)               => {},
+            Bonus::Heal(amount)             => bonus_effects.push(effect(*caster.uid(), EffectKind::Heal, amount)),
+            Bonus::Damage(amount)           => {
+                for target in targets.iter() {
+                    bonus_effects.push(effect(*target, EffectKind::Damage, amount));
+                }
+            },
+            Bonus::Afflict(affliction)      => {
+                for target in targets.iter() {
+                    let kind = EffectKind::Afflict(affliction.kind, affliction.duration);
+                    bonus_effects.push(effect(*target, kind, affliction.magnitude as usize));
+                }
+            },
+        }
+    }
+
+    bonus_effects
+}
+
+/// Applies the given effects to the world, reporting what actually occurred
+pub fn apply(world: &mut World, effects: &[Effect]) -> Vec<EffectReport> {
+    let mut reports = Vec::new();
+    let tick = world.tick();
+    let matrix = world.interactions().clone();
+
+    for effect in effects {
+        let target = match world.actor_mut(&effect.target) {
+            Some(actor) => actor,
+            None        => continue,
+        };
+
+        let magnitude = match effect.kind {
+            EffectKind::Damage                  => target.damage(effect.magnitude),
+            EffectKind::Heal                    => target.heal(effect.magnitude),
+            EffectKind::Afflict(kind, duration) => {
+                let status = StatusEffect::new(kind, effect.element, effect.source, tick, duration, effect.magnitude as f64);
+                target.apply_status(status, &matrix, tick);
+                effect.magnitude
+            },
+        };
+
+        reports.push(EffectReport {
+            target:     effect.target,
+            kind:       effect.kind,
+            magnitude,
+            defeated:   target.is_defeated(),
+        });
+    }
+
+    reports
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl From<FatigueError> for CastError {
+    fn from(src: FatigueError) -> Self {
+        CastError::Fatigue(src)
+    }
+}
+impl From<InventoryError> for CastError {
+    fn from(src: InventoryError) -> Self {
+        CastError::Inventory(src)
+    }
+}
+impl From<TargetingError> for CastError {
+    fn from(src: TargetingError) -> Self {
+        CastError::Targeting(src)
+    }
+}
+impl Error for CastError {}
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CastError::UnknownCaster(uid)           => {
+                write!(f, "Unknown caster {}", Uuid::from_bytes(*uid))
+            },
+            CastError::UnknownAbility(uid)          => {
+                write!(f, "Caster does not know ability {}", Uuid::from_bytes(*uid))
+            },
+            CastError::UnknownTarget(uid)           => {
+                write!(f, "Unknown target {}", Uuid::from_bytes(*uid))
+            },
+            CastError::Incapacitated                => {
+                write!(f, "Caster is too exhausted to act")
+            },
+            CastError::NotReady(readiness)          => {
+                write!(f, "Ability not ready: {:?}", readiness)
+            },
+            CastError::Blocked(rule)                => {
+                write!(f, "Cast blocked by rule {:?}", rule.conditions)
+            },
+            CastError::OutOfRange(pos, dist, range) => {
+                write!(f, "Target {} is {} cells away, beyond range of {}", pos, dist, range)
+            },
+            CastError::NoLineOfSight(pos)           => {
+                write!(f, "No line of sight to target {}", pos)
+            },
+            CastError::Targeting(err)               => {
+                write!(f, "Invalid target: {}", err)
+            },
+            CastError::Fatigue(err)                 => {
+                write!(f, "Fatigue: {}", err)
+            },
+            CastError::Inventory(err)               => {
+                write!(f, "Inventory: {}", err)
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ability::{
+            aspect::{
+                Aesthetics,
+                Method,
+                Morality,
+                School,
+            },
+            modifier::Modifier,
+        },
+        actor::combo::{
+            ComboBook,
+            Step,
+            Window,
+        },
+        context::Context,
+        coords::tests::pos,
+        element::Element,
+        mechanics::{
+            obstacle::Obstacle,
+            resource::State,
+        },
+        ability::timing::{
+            Activity,
+            Timing,
+        },
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn pipeline() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let mut ability = Ability::new_name_only("Zap").with_range(3);
+        ability.set_potency(20);
+        let ability_uid = *ability.uid();
+        let caster = Actor::new("Caster", pos(0, 0, 0, &ctx), 250, vec![ability]);
+        let caster_uid = *caster.uid();
+        world.add_actor(caster);
+
+        let target = Actor::new("Target", pos(2, 0, -2, &ctx), 0, Vec::new());
+        let target_uid = *target.uid();
+        world.add_actor(target);
+
+        let distant = Actor::new("Distant", pos(4, 0, -4, &ctx), 0, Vec::new());
+        let distant_uid = *distant.uid();
+        world.add_actor(distant);
+
+        // Valid cast
+        let outcome = execute(&mut world, &caster_uid, &ability_uid, &Target::Actor(target_uid)).unwrap();
+        assert_eq!(outcome.positions, vec![pos(2, 0, -2, &ctx)]);
+        assert_eq!(outcome.effects.len(), 1);
+        assert_eq!(outcome.effects[0].magnitude, 10);
+        assert_eq!(outcome.fatigue_cost, 2);
+
+        // Exhausted caster cannot bear the fatigue of another cast
+        world.actor_mut(&caster_uid).unwrap().exert(2).unwrap();
+        match execute(&mut world, &caster_uid, &ability_uid, &Target::Actor(target_uid)) {
+            Err(CastError::Fatigue(FatigueError::Overflow(254, 2))) => {},
+            other => panic!("Expected fatigue overflow, got {:?}", other),
+        }
+        world.actor_mut(&caster_uid).unwrap().recover_fatigue(std::time::Duration::from_secs(10));
+
+        // Out of range
+        match execute(&mut world, &caster_uid, &ability_uid, &Target::Actor(distant_uid)) {
+            Err(CastError::OutOfRange(_, 4, 3)) => {},
+            other => panic!("Expected OutOfRange, got {:?}", other),
+        }
+
+        // Blocked line of sight
+        world.add_obstacle(Obstacle::new(vec![pos(1, 0, -1, &ctx)], Element::Earth).unwrap());
+        match execute(&mut world, &caster_uid, &ability_uid, &Target::Actor(target_uid)) {
+            Err(CastError::NoLineOfSight(_)) => {},
+            other => panic!("Expected NoLineOfSight, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cast_time() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let mut ability = Ability::new_name_only("Bolt")
+            .with_range(3)
+            .with_timing(Timing::new(4.0, 1, 0.0, 2.0, 1.0));
+        ability.set_potency(20);
+        let ability_uid = *ability.uid();
+        let caster = Actor::new("Caster", pos(0, 0, 0, &ctx), 0, vec![ability]);
+        let caster_uid = *caster.uid();
+        world.add_actor(caster);
+
+        let target = Actor::new("Target", pos(2, 0, -2, &ctx), 0, Vec::new());
+        let target_uid = *target.uid();
+        let full_health = target.cur_health();
+        world.add_actor(target);
+
+        // The cast is paid for at once, but takes effect only once its cast time elapses
+        let outcome = execute(&mut world, &caster_uid, &ability_uid, &Target::Actor(target_uid)).unwrap();
+        assert!(outcome.pending);
+        assert!(outcome.effects.is_empty());
+        assert_eq!(world.pending_casts().len(), 1);
+        world.advance(Duration::from_secs(1));
+        assert_eq!(world.actor(&target_uid).unwrap().cur_health(), full_health);
+
+        // Cooldown and cast state survive serialization
+        let json = serde_json::to_string(world.actor(&caster_uid).unwrap()).unwrap();
+        let restored: Actor = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.activity(1.0), Activity::Casting(ability_uid, 2.0));
+        assert_eq!(restored.activity(2.5), Activity::Channeling(ability_uid, 3.0));
+        assert_eq!(restored.readiness(&ability_uid, 3.0), Some(Readiness::Cooldown(1.0)));
+        assert_eq!(restored.active_cast(), world.actor(&caster_uid).unwrap().active_cast());
+
+        // Interrupting the cast before it takes effect cancels it, without refunding the cooldown
+        assert_eq!(world.actor_mut(&caster_uid).unwrap().interrupt(1.0), Some(ability_uid));
+        world.advance(Duration::from_secs(2));
+        assert!(world.pending_casts().is_empty());
+        assert!(world.take_cast_outcomes().is_empty());
+        assert_eq!(world.actor(&target_uid).unwrap().cur_health(), full_health);
+        assert_eq!(world.actor(&caster_uid).unwrap().readiness(&ability_uid, 3.0), Some(Readiness::Cooldown(1.0)));
+
+        // An uninterrupted cast takes effect when its cast time elapses
+        world.advance(Duration::from_secs(1));
+        execute(&mut world, &caster_uid, &ability_uid, &Target::Actor(target_uid)).unwrap();
+        world.advance(Duration::from_secs(2));
+        let outcomes = world.take_cast_outcomes();
+        assert_eq!(outcomes.len(), 1);
+        assert!(!outcomes[0].pending);
+        assert_eq!(outcomes[0].effects[0].magnitude, 20);
+        assert_eq!(world.actor(&target_uid).unwrap().cur_health(), full_health - 20);
+    }
+
+    #[test]
+    fn aspect_modifiers() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let mut mend = Ability::new_name_only("Mend").with_range(3);
+        mend.set_school(School::Restoration);
+        let mut shout = Ability::new_name_only("Shout").with_range(3);
+        shout.set_method(Method::Vocal);
+        let mut veil = Ability::new_name_only("Veil").with_range(3);
+        veil.set_aesthetics(Aesthetics::Subtle);
+        let mut phantasm = Ability::new_name_only("Phantasm").with_range(3);
+        phantasm.set_school(School::Illusion);
+        let mut smite = Ability::new_name_only("Smite").with_range(3);
+        smite.set_morality(Morality::Good);
+        let mut blight = Ability::new_name_only("Blight").with_range(3);
+        blight.set_morality(Morality::Evil);
+
+        let mut abilities = vec![mend, shout, veil, phantasm, smite, blight];
+        for ability in abilities.iter_mut() {
+            ability.set_potency(20);
+        }
+        let uids: Vec<[u8; 16]> = abilities.iter().map(|ability| *ability.uid()).collect();
+        let (mend_uid, shout_uid, veil_uid, phantasm_uid, smite_uid, blight_uid) =
+            (uids[0], uids[1], uids[2], uids[3], uids[4], uids[5]);
+
+        let caster = Actor::new("Caster", pos(0, 0, 0, &ctx), 0, abilities);
+        let caster_uid = *caster.uid();
+        world.add_actor(caster);
+
+        let mut target = Actor::new("Target", pos(2, 0, -2, &ctx), 0, Vec::new());
+        target.damage(30);
+        let target_uid = *target.uid();
+        world.add_actor(target);
+        let at_target = Target::Actor(target_uid);
+
+        // Restoration heals rather than harms
+        let outcome = execute(&mut world, &caster_uid, &mend_uid, &at_target).unwrap();
+        assert_eq!(outcome.effects[0].kind, EffectKind::Heal);
+        assert_eq!(outcome.effects[0].magnitude, 20);
+        assert!(outcome.detected);
+
+        // Vocal abilities cannot be cast while silenced
+        world.actor_mut(&caster_uid).unwrap().set_silenced(true);
+        match execute(&mut world, &caster_uid, &shout_uid, &at_target) {
+            Err(CastError::Blocked(rule)) => assert_eq!(rule.modifier, Modifier::Block),
+            other => panic!("Expected Blocked, got {:?}", other),
+        }
+        world.actor_mut(&caster_uid).unwrap().set_silenced(false);
+        assert!(execute(&mut world, &caster_uid, &shout_uid, &at_target).is_ok());
+
+        // Subtle and Illusion abilities go undetected
+        assert!(!execute(&mut world, &caster_uid, &veil_uid, &at_target).unwrap().detected);
+        assert!(!execute(&mut world, &caster_uid, &phantasm_uid, &at_target).unwrap().detected);
+
+        // Abilities matching the caster's alignment are empowered, and those opposing it weakened
+        let magnitude = |world: &mut World, ability_uid: &[u8; 16]| {
+            world.actor_mut(&target_uid).unwrap().heal(100);
+            execute(world, &caster_uid, ability_uid, &at_target).unwrap().effects[0].magnitude
+        };
+        world.actor_mut(&caster_uid).unwrap().set_alignment(Morality::Good);
+        assert_eq!(magnitude(&mut world, &smite_uid), 25);
+        assert_eq!(magnitude(&mut world, &blight_uid), 10);
+        world.actor_mut(&caster_uid).unwrap().set_alignment(Morality::Evil);
+        assert_eq!(magnitude(&mut world, &smite_uid), 10);
+        assert_eq!(magnitude(&mut world, &blight_uid), 25);
+        world.actor_mut(&caster_uid).unwrap().set_alignment(Morality::Neutral);
+        assert_eq!(magnitude(&mut world, &smite_uid), 20);
+    }
+
+    #[test]
+    fn reactions() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let mut ability = Ability::new_name_only("Fireball").with_range(3);
+        ability.set_element(Element::Fire);
+        let ability_uid = *ability.uid();
+        let caster = Actor::new("Caster", pos(0, 0, 0, &ctx), 0, vec![ability]);
+        let caster_uid = *caster.uid();
+        world.add_actor(caster);
+
+        let ice_wall = Obstacle::new(vec![pos(2, 0, -2, &ctx), pos(3, 0, -3, &ctx)], Element::Ice).unwrap();
+        let wall_uid = *ice_wall.uid();
+        world.add_obstacle(ice_wall);
+
+        // Fire melts only the targeted cell of the ice wall
+        let outcome = execute(&mut world, &caster_uid, &ability_uid, &Target::Cell(pos(2, 0, -2, &ctx))).unwrap();
+        assert_eq!(outcome.mutations, vec![Mutation::RemoveObstacleCells(wall_uid, vec![pos(2, 0, -2, &ctx)])]);
+        assert_eq!(world.obstacles()[0].positions(), &vec![pos(3, 0, -3, &ctx)]);
+    }
+
+    #[test]
+    fn combos() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let mut douse = Ability::new_name_only("Douse").with_range(3);
+        douse.set_element(Element::Water);
+        douse.set_potency(10);
+        let douse_uid = *douse.uid();
+        let mut sear = Ability::new_name_only("Sear").with_range(3);
+        sear.set_element(Element::Fire);
+        sear.set_potency(10);
+        let sear_uid = *sear.uid();
+
+        let caster = Actor::new("Caster", pos(0, 0, 0, &ctx), 0, vec![douse, sear]);
+        let caster_uid = *caster.uid();
+        world.add_actor(caster);
+        let target = Actor::new("Target", pos(2, 0, -2, &ctx), 0, Vec::new());
+        let target_uid = *target.uid();
+        world.add_actor(target);
+
+        let mut combos = ComboBook::new();
+        combos.add_combo(Combo::new("Scald",
+                                    vec![Step::Element(Element::Water), Step::Element(Element::Fire)],
+                                    Window::Seconds(5.0),
+                                    vec![Bonus::Damage(7)]));
+        world.set_combos(combos);
+
+        let outcome = execute(&mut world, &caster_uid, &douse_uid, &Target::Actor(target_uid)).unwrap();
+        assert!(outcome.combos.is_empty());
+
+        world.advance(std::time::Duration::from_secs(1));
+        let outcome = execute(&mut world, &caster_uid, &sear_uid, &Target::Actor(target_uid)).unwrap();
+        assert_eq!(outcome.combos, vec![String::from("Scald")]);
+        assert_eq!(outcome.effects.len(), 2);
+        assert_eq!(outcome.effects[1].magnitude, 7);
+    }
+
+    #[test]
+    fn resources() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let mut sear = Ability::new_name_only("Sear").with_range(3);
+        sear.set_element(Element::Fire);
+        sear.set_potency(10);
+        let sear_uid = *sear.uid();
+
+        let caster = Actor::new("Caster", pos(0, 0, 0, &ctx), 0, vec![sear]);
+        let caster_uid = *caster.uid();
+        world.add_actor(caster);
+        let target = Actor::new("Target", pos(2, 0, -2, &ctx), 0, Vec::new());
+        let target_uid = *target.uid();
+        world.add_actor(target);
+
+        let brazier = Resource::new(Element::Fire, State::Full, pos(1, 0, -1, &ctx), 1);
+        let brazier_uid = *brazier.uid();
+        world.add_resource(brazier);
+        world.add_resource(Resource::new(Element::Water, State::Full, pos(0, 0, 0, &ctx), 1));
+        world.add_resource(Resource::new(Element::Fire, State::Full, pos(5, 0, -5, &ctx), 1));
+        assert_eq!(world.resources_covering(&pos(0, 0, 0, &ctx)).len(), 2);
+
+        // Only the nearby fire empowers the cast, and is drawn down by it
+        let outcome = execute(&mut world, &caster_uid, &sear_uid, &Target::Actor(target_uid)).unwrap();
+        assert_eq!(outcome.resources, vec![brazier_uid]);
+        assert_eq!(outcome.effects[0].magnitude, 14);
+        assert_eq!(world.resource(&brazier_uid).unwrap().state(), State::High);
+        assert_eq!(world.resources()[1].state(), State::Full);
+
+        // The brazier is linked to the distant fire, which is now the fuller of the two and so is drained instead
+        let distant_uid = *world.resources()[2].uid();
+        assert!(world.network().is_linked(&brazier_uid, &distant_uid));
+        let outcome = execute(&mut world, &caster_uid, &sear_uid, &Target::Actor(target_uid)).unwrap();
+        assert_eq!(outcome.resources, vec![distant_uid]);
+        assert_eq!(world.resource(&brazier_uid).unwrap().state(), State::High);
+        assert_eq!(world.resource(&distant_uid).unwrap().state(), State::High);
+    }
+
+    #[test]
+    fn reagents_and_infusion() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let mut quake = Ability::new_name_only("Quake")
+            .with_range(3)
+            .with_reagent(Essence::new(Element::Earth, 2))
+            .with_infusion(4);
+        quake.set_element(Element::Earth);
+        quake.set_potency(20);
+        let quake_uid = *quake.uid();
+
+        let caster = Actor::new("Caster", pos(0, 0, 0, &ctx), 0, vec![quake]);
+        let caster_uid = *caster.uid();
+        world.add_actor(caster);
+        let target = Actor::new("Target", pos(2, 0, -2, &ctx), 0, Vec::new());
+        let target_uid = *target.uid();
+        world.add_actor(target);
+
+        // Without reagents the ability cannot be cast
+        match execute(&mut world, &caster_uid, &quake_uid, &Target::Actor(target_uid)) {
+            Err(CastError::Inventory(InventoryError::Insufficient(Element::Earth, 2, 0))) => {},
+            other => panic!("Expected missing reagents, got {:?}", other),
+        }
+
+        // Essence left over once the reagents are set aside empowers the cast
+        let caster = world.actor_mut(&caster_uid).unwrap();
+        caster.inventory_mut().store(Essence::new(Element::Earth, 4));
+        caster.inventory_mut().store(Essence::new(Element::Fire, 3));
+        let outcome = execute(&mut world, &caster_uid, &quake_uid, &Target::Actor(target_uid)).unwrap();
+        assert_eq!(outcome.effects[0].magnitude, 22);
+        assert_eq!(outcome.essences, vec![Essence::new(Element::Earth, 2), Essence::new(Element::Earth, 2)]);
+
+        let inventory = world.actor(&caster_uid).unwrap().inventory();
+        assert_eq!(inventory.amount(Element::Earth), 0);
+        assert_eq!(inventory.total(), 3);
+    }
+}
@@ -0,0 +1,485 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ability/catalog.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the ability catalog, a data-driven collection of
+    ability definitions loaded from JSON files.
+
+    Each file contains a list of definitions, keyed by a catalog ID unique
+    across all files in the catalog. Definitions are validated as they are
+    loaded, and the catalog may be reloaded from its source files at runtime.
+    Abilities are spawned from the catalog by ID, each with a fresh UID.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use crate::{
+    ability::{
+        Ability,
+        aspect::Aspects,
+        shape::Shape,
+        timing::Timing,
+    },
+    actor::{
+        inventory::Essence,
+        status::Affliction,
+    },
+    element::Element,
+};
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Definition of a single catalogued ability
+#[derive(
+    Debug,
+    Clone,
+    Serialize, Deserialize
+)]
+#[serde(deny_unknown_fields)]
+pub struct Definition {
+    pub id:         String,
+    pub name:       String,
+    pub potency:    usize,
+    #[serde(default)]
+    pub aspects:    Aspects,
+    #[serde(default = "default_range")]
+    pub range:      usize,
+    #[serde(default)]
+    pub shape:      Shape,
+    #[serde(default)]
+    pub costs:      Costs,
+    #[serde(default)]
+    pub effects:    Vec<Affliction>,
+}
+
+/// Costs of using a catalogued ability
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Serialize, Deserialize
+)]
+#[serde(default, deny_unknown_fields)]
+pub struct Costs {
+    pub fatigue:    Option<usize>,  // Overrides the fatigue cost derived from potency and aspects
+    pub timing:     Timing,
+    pub reagents:   Vec<Essence>,   // Essences consumed from the caster's inventory by each use
+    pub infusion:   u32,            // Most essence of the ability's element infused into each use
+}
+
+/// Collection of ability definitions, along with the files they were loaded from
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    sources:        Vec<PathBuf>,
+    definitions:    HashMap<String, Definition>,
+}
+
+#[derive(Debug)]
+pub enum CatalogError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Invalid(PathBuf, String, String, &'static str),     // File, ability ID, field, reason
+    DuplicateId(PathBuf, String),
+    UnknownId(String),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Definition {
+    /// Spawns a new ability instance from this definition
+    pub fn spawn(&self) -> Ability {
+        let mut ability = Ability::new(&self.name, self.potency, self.aspects)
+            .with_range(self.range)
+            .with_shape(self.shape)
+            .with_timing(self.costs.timing);
+
+        if let Some(cost) = self.costs.fatigue {
+            ability = ability.with_fatigue_cost(cost);
+        }
+        for affliction in self.effects.iter() {
+            ability.add_affliction(*affliction);
+        }
+        for reagent in self.costs.reagents.iter() {
+            ability = ability.with_reagent(*reagent);
+        }
+        ability = ability.with_infusion(self.costs.infusion);
+
+        ability
+    }
+
+    /// Validates the definition, returning the offending field and reason on failure
+    pub fn validate(&self) -> Result<(), (String, &'static str)> {
+        if self.id.trim().is_empty() {
+            return Err(("id".to_string(), "must not be empty"));
+        }
+        if self.name.trim().is_empty() {
+            return Err(("name".to_string(), "must not be empty"));
+        }
+
+        match self.shape {
+            Shape::Line(size)       |
+            Shape::Cone(size)       |
+            Shape::Ring(size)       |
+            Shape::Blast(size)      => {
+                if size == 0 {
+                    return Err(("shape".to_string(), "size must be at least 1"));
+                }
+            },
+            Shape::Chain(jumps, _)  => {
+                if jumps == 0 {
+                    return Err(("shape".to_string(), "chain must jump at least once"));
+                }
+            },
+            Shape::Single           => {},
+        }
+
+        let timing = &self.costs.timing;
+        let durations = [
+            ("cooldown", timing.cooldown),
+            ("recharge", timing.recharge),
+            ("cast_time", timing.cast_time),
+            ("channel", timing.channel),
+        ];
+        for (field, value) in durations.iter() {
+            if !value.is_finite() || *value < 0.0 {
+                return Err((format!("costs.timing.{}", field), "must be a non-negative number of seconds"));
+            }
+        }
+        if timing.max_charges == 0 {
+            return Err(("costs.timing.max_charges".to_string(), "must be at least 1"));
+        }
+
+        for (i, reagent) in self.costs.reagents.iter().enumerate() {
+            if reagent.element == Element::Unset {
+                return Err((format!("costs.reagents[{}].element", i), "must be set"));
+            }
+            if reagent.amount == 0 {
+                return Err((format!("costs.reagents[{}].amount", i), "must be at least 1"));
+            }
+        }
+
+        for (i, effect) in self.effects.iter().enumerate() {
+            if !effect.duration.is_finite() || effect.duration <= 0.0 {
+                return Err((format!("effects[{}].duration", i), "must be a positive number of seconds"));
+            }
+            if !effect.magnitude.is_finite() || effect.magnitude < 0.0 {
+                return Err((format!("effects[{}].magnitude", i), "must be non-negative"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Catalog {
+    /// Constructs an empty catalog
+    pub fn new() -> Self {
+        Self {
+            sources:        Vec::new(),
+            definitions:    HashMap::new(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn sources(&self) -> &Vec<PathBuf> {
+        &self.sources
+    }
+
+    pub fn definitions(&self) -> &HashMap<String, Definition> {
+        &self.definitions
+    }
+
+    /// Returns a reference to the definition with the given ID, if present
+    pub fn definition(&self, id: &str) -> Option<&Definition> {
+        self.definitions.get(id)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Loads all definitions from the given file, adding it to the catalog's sources.
+    /// The catalog is left unchanged if any definition in the file is invalid.
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, CatalogError> {
+        let path = path.as_ref().to_path_buf();
+        let definitions = parse_file(&path)?;
+
+        let mut loaded = self.definitions.clone();
+        let count = insert_all(&mut loaded, &path, definitions)?;
+
+        self.definitions = loaded;
+        self.sources.push(path);
+
+        Ok(count)
+    }
+
+    /// Loads all JSON files in the given directory, in filename order
+    pub fn load_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, CatalogError> {
+        let dir = path.as_ref();
+
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|err| CatalogError::Io(dir.to_path_buf(), err))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+
+        let mut count = 0;
+        for file in files {
+            count += self.load_file(file)?;
+        }
+
+        Ok(count)
+    }
+
+    /// Reloads all definitions from the catalog's source files.
+    /// The catalog is left unchanged if any source fails to load.
+    pub fn reload(&mut self) -> Result<usize, CatalogError> {
+        let mut reloaded = HashMap::new();
+        let mut count = 0;
+        for path in self.sources.iter() {
+            count += insert_all(&mut reloaded, path, parse_file(path)?)?;
+        }
+
+        self.definitions = reloaded;
+
+        Ok(count)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Spawns a new ability instance from the definition with the given ID
+    pub fn spawn(&self, id: &str) -> Result<Ability, CatalogError> {
+        self.definitions.get(id)
+            .map(Definition::spawn)
+            .ok_or_else(|| CatalogError::UnknownId(id.to_string()))
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+fn default_range() -> usize {
+    super::DEFAULT_ABILITY_RANGE
+}
+
+/// Reads, parses and validates all definitions in the given file
+fn parse_file(path: &Path) -> Result<Vec<Definition>, CatalogError> {
+    let json = fs::read_to_string(path).map_err(|err| CatalogError::Io(path.to_path_buf(), err))?;
+    let definitions: Vec<Definition> = serde_json::from_str(&json)
+        .map_err(|err| CatalogError::Parse(path.to_path_buf(), err))?;
+
+    for definition in definitions.iter() {
+        definition.validate().map_err(|(field, reason)| {
+            CatalogError::Invalid(path.to_path_buf(), definition.id.clone(), field, reason)
+        })?;
+    }
+
+    Ok(definitions)
+}
+
+/// Inserts the given definitions, rejecting any ID already present
+fn insert_all(map:          &mut HashMap<String, Definition>,
+              path:         &Path,
+              definitions:  Vec<Definition>) -> Result<usize, CatalogError> {
+    let count = definitions.len();
+    for definition in definitions {
+        if map.contains_key(&definition.id) {
+            return Err(CatalogError::DuplicateId(path.to_path_buf(), definition.id));
+        }
+        map.insert(definition.id.clone(), definition);
+    }
+
+    Ok(count)
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *    CatalogError    *
+\*  *  *  *  *  *  *  */
+impl Error for CatalogError {}
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::Io(path, err)                     => {
+                write!(f, "{}: failed to read ability catalog: {}", path.display(), err)
+            },
+            CatalogError::Parse(path, err)                  => {
+                write!(f, "{}: failed to parse ability catalog: {}", path.display(), err)
+            },
+            CatalogError::Invalid(path, id, field, reason)  => {
+                write!(f, "{}: ability '{}': field '{}' {}", path.display(), id, field, reason)
+            },
+            CatalogError::DuplicateId(path, id)             => {
+                write!(f, "{}: ability '{}' is already defined", path.display(), id)
+            },
+            CatalogError::UnknownId(id)                     => {
+                write!(f, "No ability '{}' in catalog", id)
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        actor::status::Kind,
+        element::Element,
+    };
+
+    /// Writes the given JSON to a uniquely-named file in the system temp directory
+    fn write_temp(name: &str, json: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("cast_iron_catalog_{}_{}.json", name, std::process::id()));
+        fs::write(&path, json).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn load_and_spawn() {
+        let path = write_temp("load", r#"[
+            {
+                "id": "fireball",
+                "name": "Fireball",
+                "potency": 40,
+                "aspects": {"element": "Fire", "school": "Destruction"},
+                "range": 4,
+                "shape": {"Blast": 1},
+                "costs": {"fatigue": 12, "timing": {"cooldown": 2.0}},
+                "effects": [{"kind": "Burning", "duration": 3.0, "magnitude": 2.0}]
+            },
+            {"id": "poke", "name": "Poke", "potency": 5}
+        ]"#);
+
+        let mut catalog = Catalog::new();
+        assert_eq!(catalog.load_file(&path).unwrap(), 2);
+
+        let fireball = catalog.spawn("fireball").unwrap();
+        assert_eq!(fireball.name(), "Fireball");
+        assert_eq!(*fireball.element(), Element::Fire);
+        assert_eq!(fireball.range(), 4);
+        assert_eq!(fireball.shape(), Shape::Blast(1));
+        assert_eq!(fireball.fatigue_cost(), Some(12));
+        assert_eq!(fireball.timing().cooldown, 2.0);
+        assert_eq!(fireball.afflictions()[0].kind, Kind::Burning);
+
+        // Each spawn is a distinct instance, and omitted fields take their defaults
+        assert!(catalog.spawn("fireball").unwrap() != fireball);
+        assert_eq!(catalog.spawn("poke").unwrap().range(), 1);
+
+        match catalog.spawn("frostbolt") {
+            Err(CatalogError::UnknownId(id)) => assert_eq!(id, "frostbolt"),
+            other => panic!("Expected unknown ID error, got {:?}", other.map(|abil| abil.to_string())),
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn validation() {
+        let path = write_temp("invalid", r#"[
+            {"id": "slow", "name": "Slow", "potency": 5, "costs": {"timing": {"max_charges": 0}}}
+        ]"#);
+
+        let mut catalog = Catalog::new();
+        let err = catalog.load_file(&path).unwrap_err();
+        match &err {
+            CatalogError::Invalid(file, id, field, _reason) => {
+                assert_eq!(file, &path);
+                assert_eq!(id, "slow");
+                assert_eq!(field, "costs.timing.max_charges");
+            },
+            other => panic!("Expected invalid field error, got {:?}", other),
+        }
+        assert!(err.to_string().contains(&path.display().to_string()));
+        assert!(catalog.definitions().is_empty());
+        assert!(catalog.sources().is_empty());
+
+        // Parse errors name the offending field as well
+        fs::write(&path, r#"[{"id": "slow", "name": "Slow"}]"#).unwrap();
+        let err = catalog.load_file(&path).unwrap_err();
+        assert!(err.to_string().contains("potency"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reload() {
+        let path = write_temp("reload", r#"[{"id": "bolt", "name": "Bolt", "potency": 10}]"#);
+
+        let mut catalog = Catalog::new();
+        catalog.load_file(&path).unwrap();
+
+        fs::write(&path, r#"[{"id": "bolt", "name": "Bolt", "potency": 20}]"#).unwrap();
+        catalog.reload().unwrap();
+        assert_eq!(catalog.spawn("bolt").unwrap().potency(), 20);
+
+        // A failed reload leaves the catalog as it was
+        fs::write(&path, r#"[{"id": "bolt", "name": "", "potency": 30}]"#).unwrap();
+        assert!(catalog.reload().is_err());
+        assert_eq!(catalog.spawn("bolt").unwrap().potency(), 20);
+
+        fs::remove_file(path).unwrap();
+    }
+}
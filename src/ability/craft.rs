@@ -0,0 +1,509 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ability/craft.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module provides ability crafting: the combination of two or more
+    abilities into a new one.
+
+    Components are combined in order, the first being the primary. Elements
+    fuse pairwise according to a fusion table, potency combines according to
+    a configurable rule, and aspects merge with the primary component winning
+    any conflict that is not outright contradictory.
+
+    The crafted ability requires the reagents of all its components, infuses
+    as much essence as the most infusing component, and is as tiring as all
+    its components together should any of them fix its own fatigue cost.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+};
+
+use crate::{
+    ability::{
+        Ability,
+        aspect::*,
+        timing::Timing,
+    },
+    actor::{
+        fatigue,
+        inventory::Essence,
+        status::Affliction,
+    },
+    element::Element,
+};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default maximum number of components in a single combination
+const DEFAULT_MAX_COMPONENTS: usize = 4;
+
+/// Default scaling applied to each successive component under diminishing returns
+const DEFAULT_DIMINISHING_FACTOR: f64 = 0.5;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Result of fusing two elements
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Fusion {
+    pub element:            Element,
+    pub potency_multiplier: f64,
+}
+
+/// Table of element fusions. Pairs are unordered, and unlisted pairs of differing elements do not fuse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FusionTable {
+    entries: HashMap<(Element, Element), Fusion>,
+}
+
+/// Enumeration of the rules by which component potencies combine
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PotencyRule {
+    Sum,                // Potencies are added
+    Max,                // Potency of the strongest component
+    Mean,               // Average potency of all components
+    Diminishing(f64),   // Strongest first at full potency, each successive component scaled by a further factor
+}
+
+/// Configuration of ability crafting
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crafter {
+    fusions:        FusionTable,
+    potency_rule:   PotencyRule,
+    max_components: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CraftError {
+    TooFewComponents(usize),
+    TooManyComponents(usize, usize),
+    IncompatibleElements(Element, Element),
+    ConflictingMorality(Morality, Morality),
+    ConflictingSchools(School, School),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Fusion {
+    /// Fully-qualified constructor
+    pub fn new(element: Element, potency_multiplier: f64) -> Self {
+        Self {element, potency_multiplier}
+    }
+}
+
+impl FusionTable {
+    /// Constructs an empty table, under which only identical (or unset) elements fuse
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the fusion of the given elements, if they fuse
+    pub fn get(&self, a: Element, b: Element) -> Option<Fusion> {
+        if a == b || b == Element::Unset {
+            return Some(Fusion::new(a, 1.0));
+        }
+        if a == Element::Unset {
+            return Some(Fusion::new(b, 1.0));
+        }
+
+        self.entries.get(&key(a, b)).cloned()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the fusion of the given pair of elements, replacing any existing entry
+    pub fn set(&mut self, a: Element, b: Element, fusion: Fusion) {
+        self.entries.insert(key(a, b), fusion);
+    }
+}
+
+impl PotencyRule {
+    /// Combines the given potencies according to the rule
+    pub fn combine(&self, potencies: &[usize]) -> f64 {
+        if potencies.is_empty() {
+            return 0.0;
+        }
+
+        match *self {
+            PotencyRule::Sum                    => potencies.iter().sum::<usize>() as f64,
+            PotencyRule::Max                    => *potencies.iter().max().unwrap() as f64,
+            PotencyRule::Mean                   => potencies.iter().sum::<usize>() as f64 / potencies.len() as f64,
+            PotencyRule::Diminishing(factor)    => {
+                let mut sorted = potencies.to_vec();
+                sorted.sort_unstable_by(|a, b| b.cmp(a));
+
+                sorted.iter()
+                    .enumerate()
+                    .map(|(i, &potency)| potency as f64 * factor.powi(i as i32))
+                    .sum()
+            },
+        }
+    }
+}
+
+impl Crafter {
+    /// Fully-qualified constructor
+    pub fn new(fusions: FusionTable, potency_rule: PotencyRule, max_components: usize) -> Self {
+        Self {fusions, potency_rule, max_components}
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn fusions(&self) -> &FusionTable {
+        &self.fusions
+    }
+
+    pub fn potency_rule(&self) -> PotencyRule {
+        self.potency_rule
+    }
+
+    pub fn max_components(&self) -> usize {
+        self.max_components
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Combines the given abilities into a new ability of the given name.
+    /// The first component is the primary, and wins any resolvable aspect conflict.
+    pub fn combine(&self, name: &str, components: &[&Ability]) -> Result<Ability, CraftError> {
+        if components.len() < 2 {
+            return Err(CraftError::TooFewComponents(components.len()));
+        }
+        if components.len() > self.max_components {
+            return Err(CraftError::TooManyComponents(components.len(), self.max_components));
+        }
+
+        // Fuse elements pairwise, accumulating the potency multipliers of each fusion
+        let mut fusion = Fusion::new(*components[0].element(), 1.0);
+        for component in components.iter().skip(1) {
+            let element = *component.element();
+            let next = self.fusions.get(fusion.element, element)
+                .ok_or(CraftError::IncompatibleElements(fusion.element, element))?;
+            fusion = Fusion::new(next.element, fusion.potency_multiplier * next.potency_multiplier);
+        }
+
+        let mut aspects = *components[0].aspects();
+        for component in components.iter().skip(1) {
+            aspects = merge_aspects(&aspects, component.aspects())?;
+        }
+        aspects.element = fusion.element;
+
+        let potencies: Vec<usize> = components.iter().map(|component| component.potency()).collect();
+        let potency = (self.potency_rule.combine(&potencies) * fusion.potency_multiplier).round() as usize;
+
+        let mut ability = Ability::new(name, potency, aspects)
+            .with_range(components.iter().map(|component| component.range()).min().unwrap())
+            .with_shape(components[0].shape())
+            .with_timing(merge_timings(components));
+
+        for affliction in merge_afflictions(components) {
+            ability.add_affliction(affliction);
+        }
+        for reagent in merge_reagents(components) {
+            ability = ability.with_reagent(reagent);
+        }
+        ability = ability.with_infusion(components.iter().map(|component| component.infusion()).max().unwrap());
+
+        // Components with a fixed fatigue cost fix the cost of the whole
+        if components.iter().any(|component| component.fatigue_cost().is_some()) {
+            ability = ability.with_fatigue_cost(components.iter().map(|component| fatigue::cost(component)).sum());
+        }
+
+        Ok(ability)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Combines the given abilities using the default crafting configuration
+pub fn combine(name: &str, components: &[&Ability]) -> Result<Ability, CraftError> {
+    Crafter::default().combine(name, components)
+}
+
+/// Merges the secondary aspects into the primary. Unset aspects always yield to set ones.
+pub fn merge_aspects(primary: &Aspects, secondary: &Aspects) -> Result<Aspects, CraftError> {
+    let morality = match (primary.morality, secondary.morality) {
+        (Morality::Good, Morality::Evil)                |
+        (Morality::Evil, Morality::Good)                => {
+            return Err(CraftError::ConflictingMorality(primary.morality, secondary.morality));
+        },
+        // Neutrality yields only to a stronger conviction
+        (Morality::Unset, other)                        |
+        (Morality::Neutral, other @ Morality::Good)     |
+        (Morality::Neutral, other @ Morality::Evil)     => other,
+        (own, _)                                        => own,
+    };
+
+    let school = match (primary.school, secondary.school) {
+        (School::Destruction, School::Restoration)  |
+        (School::Restoration, School::Destruction)  => {
+            return Err(CraftError::ConflictingSchools(primary.school, secondary.school));
+        },
+        (School::Unset, other)                      => other,
+        (own, _)                                    => own,
+    };
+
+    let aesthetics = if primary.aesthetics == Aesthetics::Unset { secondary.aesthetics } else { primary.aesthetics };
+    let method = if primary.method == Method::Unset { secondary.method } else { primary.method };
+
+    Ok(Aspects::new(aesthetics, primary.element, method, morality, school))
+}
+
+/// Merges the timings of the given components. The result is as slow as its slowest component,
+/// and must be cast through in full.
+fn merge_timings(components: &[&Ability]) -> Timing {
+    let timings: Vec<&Timing> = components.iter().map(|component| component.timing()).collect();
+
+    Timing::new(
+        timings.iter().map(|timing| timing.cooldown).fold(0.0, f64::max),
+        timings.iter().map(|timing| timing.max_charges).min().unwrap_or(1),
+        timings.iter().map(|timing| timing.recharge).fold(0.0, f64::max),
+        timings.iter().map(|timing| timing.cast_time).sum(),
+        timings.iter().map(|timing| timing.channel).fold(0.0, f64::max),
+    )
+}
+
+/// Merges the afflictions of the given components, keeping the strongest of each kind
+fn merge_afflictions(components: &[&Ability]) -> Vec<Affliction> {
+    let mut merged: Vec<Affliction> = Vec::new();
+
+    for affliction in components.iter().flat_map(|component| component.afflictions().iter()) {
+        match merged.iter_mut().find(|existing| existing.kind == affliction.kind) {
+            Some(existing)  => {
+                existing.duration = existing.duration.max(affliction.duration);
+                existing.magnitude = existing.magnitude.max(affliction.magnitude);
+            },
+            None            => merged.push(*affliction),
+        }
+    }
+
+    merged
+}
+
+/// Merges the reagents of the given components, totalling those of each element
+fn merge_reagents(components: &[&Ability]) -> Vec<Essence> {
+    let mut merged: Vec<Essence> = Vec::new();
+
+    for reagent in components.iter().flat_map(|component| component.reagents().iter()) {
+        match merged.iter_mut().find(|existing| existing.element == reagent.element) {
+            Some(existing)  => existing.amount += reagent.amount,
+            None            => merged.push(*reagent),
+        }
+    }
+
+    merged
+}
+
+/// Normalizes an unordered pair of elements into a table key
+fn key(a: Element, b: Element) -> (Element, Element) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+/*  *  *  *  *  *  *  *\
+ *    FusionTable     *
+\*  *  *  *  *  *  *  */
+impl Default for FusionTable {
+    /// Constructs the default CastIron fusion table
+    fn default() -> Self {
+        let table = [
+            (Element::Fire,     Element::Wind,      Element::Fire,      1.50),
+            (Element::Fire,     Element::Earth,     Element::Earth,     1.25),
+            (Element::Fire,     Element::Light,     Element::Light,     1.10),
+            (Element::Ice,      Element::Water,     Element::Ice,       1.00),
+            (Element::Ice,      Element::Wind,      Element::Ice,       1.25),
+            (Element::Wind,     Element::Water,     Element::Water,     1.10),
+            (Element::Wind,     Element::Electric,  Element::Electric,  1.25),
+            (Element::Wind,     Element::Earth,     Element::Earth,     1.00),
+            (Element::Water,    Element::Electric,  Element::Electric,  1.50),
+            (Element::Water,    Element::Earth,     Element::Earth,     1.00),
+            (Element::Earth,    Element::Dark,      Element::Dark,      1.10),
+        ];
+
+        let mut fusions = Self::new();
+        for &(a, b, element, multiplier) in table.iter() {
+            fusions.set(a, b, Fusion::new(element, multiplier));
+        }
+
+        fusions
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *      Crafter       *
+\*  *  *  *  *  *  *  */
+impl Default for Crafter {
+    fn default() -> Self {
+        Self {
+            fusions:        FusionTable::default(),
+            potency_rule:   PotencyRule::Diminishing(DEFAULT_DIMINISHING_FACTOR),
+            max_components: DEFAULT_MAX_COMPONENTS,
+        }
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *     CraftError     *
+\*  *  *  *  *  *  *  */
+impl Error for CraftError {}
+impl fmt::Display for CraftError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CraftError::TooFewComponents(count)             => {
+                write!(f, "At least 2 abilities are required to craft, got {}", count)
+            },
+            CraftError::TooManyComponents(count, max)       => {
+                write!(f, "At most {} abilities may be combined, got {}", max, count)
+            },
+            CraftError::IncompatibleElements(a, b)          => {
+                write!(f, "{:?} and {:?} do not fuse", a, b)
+            },
+            CraftError::ConflictingMorality(a, b)           => {
+                write!(f, "{:?} and {:?} abilities cannot be combined", a, b)
+            },
+            CraftError::ConflictingSchools(a, b)            => {
+                write!(f, "{:?} and {:?} abilities cannot be combined", a, b)
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ability(name: &str, potency: usize, element: Element, morality: Morality, school: School) -> Ability {
+        Ability::new(name, potency, Aspects::new(Aesthetics::Unset, element, Method::Unset, morality, school))
+    }
+
+    #[test]
+    fn fusion() {
+        let fire = ability("Fire", 100, Element::Fire, Morality::Neutral, School::Destruction);
+        let wind = ability("Wind", 50, Element::Wind, Morality::Unset, School::Unset);
+        let water = ability("Water", 40, Element::Water, Morality::Good, School::Unset);
+        let ice = ability("Ice", 60, Element::Ice, Morality::Unset, School::Alteration);
+
+        // Fire + Wind yields stronger Fire, regardless of order
+        let firestorm = combine("Firestorm", &[&fire, &wind]).unwrap();
+        assert_eq!(*firestorm.element(), Element::Fire);
+        assert_eq!(firestorm.potency(), 188);
+        assert_eq!(*firestorm.school(), School::Destruction);
+        assert_eq!(*firestorm.morality(), Morality::Neutral);
+        assert_eq!(*combine("Firestorm", &[&wind, &fire]).unwrap().element(), Element::Fire);
+
+        // Water + Ice yields Ice, taking the stronger conviction and the primary's school
+        let hail = combine("Hail", &[&water, &ice]).unwrap();
+        assert_eq!(*hail.element(), Element::Ice);
+        assert_eq!(*hail.morality(), Morality::Good);
+        assert_eq!(*hail.school(), School::Alteration);
+
+        assert_eq!(combine("Steam", &[&fire, &water]).unwrap_err(),
+                   CraftError::IncompatibleElements(Element::Fire, Element::Water));
+    }
+
+    #[test]
+    fn rejections() {
+        let smite = ability("Smite", 10, Element::Light, Morality::Good, School::Destruction);
+        let curse = ability("Curse", 10, Element::Light, Morality::Evil, School::Unset);
+        let mend = ability("Mend", 10, Element::Light, Morality::Unset, School::Restoration);
+
+        assert_eq!(combine("Solo", &[&smite]).unwrap_err(), CraftError::TooFewComponents(1));
+        assert_eq!(combine("Grey", &[&smite, &curse]).unwrap_err(),
+                   CraftError::ConflictingMorality(Morality::Good, Morality::Evil));
+        assert_eq!(combine("Paradox", &[&smite, &mend]).unwrap_err(),
+                   CraftError::ConflictingSchools(School::Destruction, School::Restoration));
+        assert_eq!(combine("Pile", &[&smite, &smite, &smite, &smite, &smite]).unwrap_err(),
+                   CraftError::TooManyComponents(5, DEFAULT_MAX_COMPONENTS));
+    }
+
+    #[test]
+    fn merging() {
+        let neutral = Aspects::new(Aesthetics::Unset, Element::Fire, Method::Unset, Morality::Neutral, School::Unset);
+        let unset = Aspects::new(Aesthetics::Subtle, Element::Fire, Method::Unset, Morality::Unset, School::Unset);
+        let evil = Aspects::new(Aesthetics::Unset, Element::Fire, Method::Unset, Morality::Evil, School::Unset);
+
+        // Neutrality yields to good or evil, but not to an unset morality
+        assert_eq!(merge_aspects(&neutral, &unset).unwrap().morality, Morality::Neutral);
+        assert_eq!(merge_aspects(&neutral, &evil).unwrap().morality, Morality::Evil);
+        assert_eq!(merge_aspects(&unset, &neutral).unwrap().morality, Morality::Neutral);
+
+        // Reagents are totalled, infusion taken from the most infusing component, and fixed fatigue costs summed
+        let kindle = ability("Kindle", 10, Element::Fire, Morality::Unset, School::Unset)
+            .with_reagent(Essence::new(Element::Fire, 2))
+            .with_infusion(3)
+            .with_fatigue_cost(4);
+        let gust = ability("Gust", 10, Element::Wind, Morality::Unset, School::Unset)
+            .with_reagent(Essence::new(Element::Fire, 1))
+            .with_reagent(Essence::new(Element::Wind, 2))
+            .with_infusion(1);
+        let blaze = combine("Blaze", &[&kindle, &gust]).unwrap();
+        assert_eq!(blaze.reagents(), &vec![Essence::new(Element::Fire, 3), Essence::new(Element::Wind, 2)]);
+        assert_eq!(blaze.infusion(), 3);
+        assert_eq!(blaze.fatigue_cost(), Some(4 + fatigue::cost(&gust)));
+        assert_eq!(combine("Draught", &[&gust, &gust]).unwrap().fatigue_cost(), None);
+    }
+
+    #[test]
+    fn potency_rules() {
+        let potencies = [10, 40, 20];
+
+        assert_eq!(PotencyRule::Sum.combine(&potencies), 70.0);
+        assert_eq!(PotencyRule::Max.combine(&potencies), 40.0);
+        assert_eq!(PotencyRule::Diminishing(0.5).combine(&potencies), 40.0 + 10.0 + 2.5);
+    }
+}
@@ -25,6 +25,10 @@ Purpose:
 use std::fmt;
 
 use crate::{
+    actor::{
+        inventory::Essence,
+        status::Affliction,
+    },
     context::Context,
     element::Element,
     Randomizable,
@@ -44,6 +48,29 @@ use uuid::Uuid;
 
 pub mod aspect;
 use self::aspect::*;
+pub mod cast;
+pub mod catalog;
+pub mod craft;
+pub mod modifier;
+pub mod shape;
+use self::shape::Shape;
+pub mod timing;
+use self::timing::{
+    Cooldown,
+    Readiness,
+    Timing,
+};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default range of an ability (in cells)
+const DEFAULT_ABILITY_RANGE: usize = 1;
+
+/// Maximum range of a randomly-generated ability (in cells)
+const MAX_RAND_ABILITY_RANGE: usize = 6;
 
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -51,12 +78,20 @@ use self::aspect::*;
 ///////////////////////////////////////////////////////////////////////////////
 
 /// Struct containing all necessary data fields to define an ability for use in CastIron
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ability {
     uid:        [u8; 16],
     name:       String,
     aspects:    Aspects,
     potency:    usize,
+    range:      usize,
+    shape:      Shape,
+    timing:     Timing,
+    cooldown:   Cooldown,
+    inflicts:   Vec<Affliction>,
+    fatigue:    Option<usize>,  // Fatigue cost overriding the one derived from potency and aspects
+    reagents:   Vec<Essence>,   // Essences consumed from the caster's inventory by each use
+    infusion:   u32,            // Most essence of the ability's element infused to empower each use
 }
 
 
@@ -66,31 +101,94 @@ pub struct Ability {
 
 impl Ability {
     /// Fully-qualified constructor
-    pub fn new(name: &'static str, potency: usize, aspects: Aspects) -> Self {
+    pub fn new(name: &str, potency: usize, aspects: Aspects) -> Self {
         Self {
             uid:        *Uuid::new_v4().as_bytes(),
             name:       name.to_string(),
             potency,
             aspects,
+            range:      DEFAULT_ABILITY_RANGE,
+            shape:      Shape::default(),
+            timing:     Timing::default(),
+            cooldown:   Cooldown::default(),
+            inflicts:   Vec::new(),
+            fatigue:    None,
+            reagents:   Vec::new(),
+            infusion:   0,
         }
     }
     /// Name-only constructor
-    pub fn new_name_only(name: &'static str) -> Self {
+    pub fn new_name_only(name: &str) -> Self {
         Self {
             uid:        *Uuid::new_v4().as_bytes(),
             name:       name.to_string(),
             potency:    0,
             aspects:    Aspects::default(),
+            range:      DEFAULT_ABILITY_RANGE,
+            shape:      Shape::default(),
+            timing:     Timing::default(),
+            cooldown:   Cooldown::default(),
+            inflicts:   Vec::new(),
+            fatigue:    None,
+            reagents:   Vec::new(),
+            infusion:   0,
         }
     }
 
 
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn with_range(mut self, range: usize) -> Self {
+        self.range = range;
+
+        self
+    }
+
+    pub fn with_shape(mut self, shape: Shape) -> Self {
+        self.shape = shape;
+
+        self
+    }
+
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.set_timing(timing);
+
+        self
+    }
+
+    pub fn with_affliction(mut self, affliction: Affliction) -> Self {
+        self.inflicts.push(affliction);
+
+        self
+    }
+
+    pub fn with_fatigue_cost(mut self, cost: usize) -> Self {
+        self.fatigue = Some(cost);
+
+        self
+    }
+
+    pub fn with_reagent(mut self, reagent: Essence) -> Self {
+        self.reagents.push(reagent);
+
+        self
+    }
+
+    pub fn with_infusion(mut self, infusion: u32) -> Self {
+        self.infusion = infusion;
+
+        self
+    }
+
+
     /*  *  *  *  *  *  *  *\
      *  Mutator Methods   *
     \*  *  *  *  *  *  *  */
 
     // Name the ability
-    pub fn set_name (&mut self, name: &'static str) {
+    pub fn set_name (&mut self, name: &str) {
         self.name.clear();
         self.name.push_str(name);
     }
@@ -99,6 +197,31 @@ impl Ability {
         self.potency = potency;
     }
 
+    pub fn set_range (&mut self, range: usize) {
+        self.range = range;
+    }
+
+    pub fn set_shape (&mut self, shape: Shape) {
+        self.shape = shape;
+    }
+
+    // Sets the timing of the ability, resetting its cooldown state and banking at least one charge
+    pub fn set_timing (&mut self, mut timing: Timing) {
+        timing.max_charges = u8::max(timing.max_charges, 1);
+        self.timing = timing;
+        self.cooldown = Cooldown::new(&timing);
+    }
+
+    // Spends a charge of the ability and begins its cooldown at the given tick
+    pub fn trigger (&mut self, tick: f64) -> Result<(), Readiness> {
+        self.cooldown.trigger(&self.timing, tick)
+    }
+
+    // Adds a status effect to be inflicted upon the actors the ability affects
+    pub fn add_affliction (&mut self, affliction: Affliction) {
+        self.inflicts.push(affliction);
+    }
+
     pub fn set_aspects(&mut self, aspects: Aspects) {
         self.aspects = aspects;
     }
@@ -143,6 +266,51 @@ impl Ability {
         self.potency
     }
 
+    // Returns range of the ability (in cells)
+    pub fn range (&self) -> usize {
+        self.range
+    }
+
+    // Returns the area-of-effect shape of the ability
+    pub fn shape (&self) -> Shape {
+        self.shape
+    }
+
+    // Returns a reference to the timing configuration of the ability
+    pub fn timing (&self) -> &Timing {
+        &self.timing
+    }
+
+    // Returns the number of charges of the ability available at the given tick
+    pub fn charges (&self, tick: f64) -> u8 {
+        self.cooldown.charges(&self.timing, tick)
+    }
+
+    // Returns the readiness of the ability at the given tick
+    pub fn readiness (&self, tick: f64) -> Readiness {
+        self.cooldown.readiness(&self.timing, tick)
+    }
+
+    // Returns a reference to the status effects the ability inflicts
+    pub fn afflictions (&self) -> &Vec<Affliction> {
+        &self.inflicts
+    }
+
+    // Returns the fatigue cost of the ability, if it overrides the derived cost
+    pub fn fatigue_cost (&self) -> Option<usize> {
+        self.fatigue
+    }
+
+    // Returns a reference to the essences consumed by each use of the ability
+    pub fn reagents (&self) -> &Vec<Essence> {
+        &self.reagents
+    }
+
+    // Returns the most essence of the ability's element infused into each use of the ability
+    pub fn infusion (&self) -> u32 {
+        self.infusion
+    }
+
     // Returns a reference to the Aspects of the ability
     pub fn aspects (&self) -> &Aspects {
         &self.aspects
@@ -203,6 +371,9 @@ impl Randomizable for Ability {
         // Generate random potency
         let potency: usize = rng.gen();
 
+        // Generate random range
+        let range: usize = rng.gen_range(DEFAULT_ABILITY_RANGE, MAX_RAND_ABILITY_RANGE+1);
+
         // Generate random aspects
         let aspects = Aspects::rand(ctx);
 
@@ -211,6 +382,14 @@ impl Randomizable for Ability {
             name,
             potency,
             aspects,
+            range,
+            shape:      Shape::default(),
+            timing:     Timing::default(),
+            cooldown:   Cooldown::default(),
+            inflicts:   Vec::new(),
+            fatigue:    None,
+            reagents:   Vec::new(),
+            infusion:   0,
         }
     }
 }
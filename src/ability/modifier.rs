@@ -0,0 +1,324 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ability/modifier.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the rules through which an ability's aspects modify
+    the outcome of casting it.
+
+    A rule pairs a set of conditions with a modifier. Whenever all of a
+    rule's conditions hold for a cast, its modifier is applied to the
+    resolution. Rule tables are plain data, so games may extend or replace
+    the default table, or load their own from JSON.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use crate::{
+    ability::{
+        aspect::*,
+        cast::EffectKind,
+    },
+    actor::Actor,
+};
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of conditions that may be placed on a rule
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Condition {
+    Aesthetics(Aesthetics),
+    Method(Method),
+    Morality(Morality),
+    School(School),
+    CasterAlignment(Morality),
+    CasterSilenced,
+}
+
+/// Enumeration of modifications a rule may make to a cast
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Modifier {
+    Heal,           // Effects restore health rather than dealing damage
+    Block,          // The cast is prevented entirely
+    Conceal,        // The cast goes undetected by onlookers
+    Scale(f64),     // Potency is multiplied by the given factor
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Rule {
+    pub conditions: Vec<Condition>,
+    pub modifier:   Modifier,
+}
+
+/// Ordered table of rules, evaluated in full for every cast
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct RuleTable {
+    rules: Vec<Rule>,
+}
+
+/// Circumstances of the caster relevant to rule evaluation
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Circumstances {
+    pub alignment:  Morality,
+    pub silenced:   bool,
+}
+
+/// The combined result of all rules applicable to a cast
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolution {
+    pub kind:               EffectKind,
+    pub potency_multiplier: f64,
+    pub detected:           bool,
+    pub blocked_by:         Option<Rule>,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Rule {
+    /// Fully-qualified constructor
+    pub fn new(conditions: Vec<Condition>, modifier: Modifier) -> Self {
+        Self {conditions, modifier}
+    }
+
+    /// Determines if all of the rule's conditions hold
+    pub fn applies(&self, aspects: &Aspects, circumstances: &Circumstances) -> bool {
+        self.conditions.iter().all(|condition| condition.holds(aspects, circumstances))
+    }
+}
+
+impl Condition {
+    /// Determines if the condition holds for the given aspects and circumstances
+    pub fn holds(&self, aspects: &Aspects, circumstances: &Circumstances) -> bool {
+        match *self {
+            Condition::Aesthetics(aesthetics)   => aspects.aesthetics == aesthetics,
+            Condition::Method(method)           => aspects.method == method,
+            Condition::Morality(morality)       => aspects.morality == morality,
+            Condition::School(school)           => aspects.school == school,
+            Condition::CasterAlignment(align)   => circumstances.alignment == align,
+            Condition::CasterSilenced           => circumstances.silenced,
+        }
+    }
+}
+
+impl RuleTable {
+    /// Constructs an empty rule table, under which aspects have no effect
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+        }
+    }
+
+    /// Constructs a rule table from the given JSON
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn rules(&self) -> &Vec<Rule> {
+        &self.rules
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn add_rule(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Applies every applicable rule to produce the resolution of a cast
+    pub fn resolve(&self, aspects: &Aspects, circumstances: &Circumstances) -> Resolution {
+        let mut resolution = Resolution::default();
+
+        for rule in self.rules.iter().filter(|rule| rule.applies(aspects, circumstances)) {
+            match rule.modifier {
+                Modifier::Heal          => resolution.kind = EffectKind::Heal,
+                Modifier::Conceal       => resolution.detected = false,
+                Modifier::Scale(factor) => resolution.potency_multiplier *= factor,
+                Modifier::Block         => {
+                    // Report the first blocking rule only
+                    if resolution.blocked_by.is_none() {
+                        resolution.blocked_by = Some(rule.clone());
+                    }
+                },
+            }
+        }
+
+        resolution
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+/*  *  *  *  *  *  *  *\
+ *     RuleTable      *
+\*  *  *  *  *  *  *  */
+impl Default for RuleTable {
+    /// Constructs the default CastIron rule table
+    fn default() -> Self {
+        let rules = vec![
+            // Aesthetics
+            Rule::new(vec![Condition::Aesthetics(Aesthetics::Subtle)],      Modifier::Conceal),
+            Rule::new(vec![Condition::Aesthetics(Aesthetics::Impressive)],  Modifier::Scale(1.10)),
+            Rule::new(vec![Condition::Aesthetics(Aesthetics::Ugly)],        Modifier::Scale(0.90)),
+            // Method
+            Rule::new(vec![Condition::Method(Method::Vocal), Condition::CasterSilenced], Modifier::Block),
+            Rule::new(vec![Condition::Method(Method::Staff)],               Modifier::Scale(1.10)),
+            // Morality vs. caster alignment
+            Rule::new(vec![Condition::Morality(Morality::Good), Condition::CasterAlignment(Morality::Good)], Modifier::Scale(1.25)),
+            Rule::new(vec![Condition::Morality(Morality::Good), Condition::CasterAlignment(Morality::Evil)], Modifier::Scale(0.50)),
+            Rule::new(vec![Condition::Morality(Morality::Evil), Condition::CasterAlignment(Morality::Evil)], Modifier::Scale(1.25)),
+            Rule::new(vec![Condition::Morality(Morality::Evil), Condition::CasterAlignment(Morality::Good)], Modifier::Scale(0.50)),
+            // School
+            Rule::new(vec![Condition::School(School::Restoration)],         Modifier::Heal),
+            Rule::new(vec![Condition::School(School::Destruction)],         Modifier::Scale(1.20)),
+            Rule::new(vec![Condition::School(School::Illusion)],            Modifier::Conceal),
+        ];
+
+        Self {rules}
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *   Circumstances    *
+\*  *  *  *  *  *  *  */
+impl From<&Actor> for Circumstances {
+    fn from(src: &Actor) -> Self {
+        Self {
+            alignment:  src.alignment(),
+            silenced:   src.is_silenced(),
+        }
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *     Resolution     *
+\*  *  *  *  *  *  *  */
+impl Default for Resolution {
+    fn default() -> Self {
+        Self {
+            kind:               EffectKind::Damage,
+            potency_multiplier: 1.0,
+            detected:           true,
+            blocked_by:         None,
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::element::Element;
+
+    fn aspects(aesthetics: Aesthetics, method: Method, morality: Morality, school: School) -> Aspects {
+        Aspects::new(aesthetics, Element::Unset, method, morality, school)
+    }
+
+    const CALM: Circumstances = Circumstances {alignment: Morality::Neutral, silenced: false};
+
+    #[test]
+    fn resolution() {
+        let table = RuleTable::default();
+
+        // Restoration heals, and Subtle abilities go undetected
+        let mend = aspects(Aesthetics::Subtle, Method::Unset, Morality::Unset, School::Restoration);
+        let resolution = table.resolve(&mend, &CALM);
+        assert_eq!(resolution.kind, EffectKind::Heal);
+        assert!(!resolution.detected);
+        assert_eq!(resolution.potency_multiplier, 1.0);
+
+        // Scale multipliers stack
+        let smite = aspects(Aesthetics::Impressive, Method::Staff, Morality::Good, School::Destruction);
+        let devout = Circumstances {alignment: Morality::Good, silenced: false};
+        let resolution = table.resolve(&smite, &devout);
+        assert_eq!(resolution.kind, EffectKind::Damage);
+        assert!(resolution.detected);
+        assert!((resolution.potency_multiplier - 1.10 * 1.10 * 1.25 * 1.20).abs() < 1e-9);
+        assert_eq!(resolution.blocked_by, None);
+
+        // Only the first blocking rule is reported
+        let mut table = RuleTable::new();
+        let silenced = Rule::new(vec![Condition::CasterSilenced], Modifier::Block);
+        let vocal = Rule::new(vec![Condition::Method(Method::Vocal)], Modifier::Block);
+        table.add_rule(silenced.clone());
+        table.add_rule(vocal);
+        let shout = aspects(Aesthetics::Unset, Method::Vocal, Morality::Unset, School::Unset);
+        let gagged = Circumstances {alignment: Morality::Neutral, silenced: true};
+        assert_eq!(table.resolve(&shout, &gagged).blocked_by, Some(silenced));
+        assert_eq!(table.resolve(&shout, &CALM).blocked_by.unwrap().conditions, vec![Condition::Method(Method::Vocal)]);
+    }
+
+    #[test]
+    fn json() {
+        let table = RuleTable::default();
+        let json = serde_json::to_string(&table).unwrap();
+        assert_eq!(RuleTable::from_json(&json).unwrap(), table);
+
+        assert!(RuleTable::from_json("{\"rules\": [{\"conditions\": [], \"modifier\": \"Explode\"}]}").is_err());
+        assert!(RuleTable::from_json("{\"rules\": ").is_err());
+    }
+}
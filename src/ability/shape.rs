@@ -0,0 +1,194 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ability/shape.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the area-of-effect shapes of CastIron abilities.
+
+    Shapes resolve to a set of positions given the caster's position, the
+    targeted position, and the direction the caster is facing. Line and cone
+    shapes emanate from the caster, while all other shapes are centered on
+    the target. Resolved positions are always clipped to the grid.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::f32::consts::PI;
+
+use crate::{
+    context::Context,
+    coords,
+    hex_directions,
+};
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Half-width of a cone (in radians), padded slightly to include cells lying exactly on the edge
+const CONE_HALF_WIDTH: f32 = PI/6.0 + 1e-4;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of area-of-effect shapes
+#[derive(
+    Debug,
+    Default,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Shape {
+    #[default]
+    Single,                 // The targeted cell only
+    Line(usize),            // Cells extending from the caster in the facing direction (length)
+    Cone(usize),            // Cells within a 60 degree wedge from the caster in the facing direction (length)
+    Ring(usize),            // Cells at exactly the given distance from the target (radius)
+    Blast(usize),           // Cells within the given distance of the target (radius)
+    Chain(usize, usize),    // Target, then successive nearest candidates (jumps, max distance per jump)
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Shape {
+    /// Resolves the shape into the set of positions it covers.
+    /// Candidates are the positions a Chain shape may jump between, and are ignored by other shapes.
+    pub fn resolve(&self,
+                   caster:      &coords::Position,
+                   target:      &coords::Position,
+                   facing:      hex_directions::Side,
+                   candidates:  &[coords::Position],
+                   ctx:         &Context) -> Vec<coords::Position> {
+        match *self {
+            Shape::Single               => vec![*target],
+            Shape::Line(length)         => Self::line(caster, facing, length, ctx),
+            Shape::Cone(length)         => Self::cone(caster, facing, length, ctx),
+            Shape::Ring(radius)         => target.ring(radius, ctx),
+            Shape::Blast(radius)        => target.within_radius(radius, ctx),
+            Shape::Chain(jumps, reach)  => Self::chain(target, jumps, reach, candidates),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Helper Methods    *
+    \*  *  *  *  *  *  *  */
+
+    fn line(caster: &coords::Position, facing: hex_directions::Side, length: usize, ctx: &Context) -> Vec<coords::Position> {
+        let trans = coords::Translation::from(facing);
+        let mut cur_pos = *caster;
+        let mut positions = Vec::new();
+
+        for _i in 0 .. length {
+            // Stop at the edge of the grid
+            if cur_pos.translate(&trans, ctx).is_err() {
+                break;
+            }
+            positions.push(cur_pos);
+        }
+
+        positions
+    }
+
+    fn cone(caster: &coords::Position, facing: hex_directions::Side, length: usize, ctx: &Context) -> Vec<coords::Position> {
+        let facing_angle: f32 = facing.into();
+
+        caster.within_radius(length, ctx).into_iter()
+            .filter(|pos| pos != caster)
+            .filter(|pos| {
+                let bearing = caster.delta_to(pos).bearing();
+                let diff = (bearing - facing_angle).abs();
+
+                f32::min(diff, 2.0*PI - diff) <= CONE_HALF_WIDTH
+            })
+            .collect()
+    }
+
+    fn chain(target: &coords::Position, jumps: usize, reach: usize, candidates: &[coords::Position]) -> Vec<coords::Position> {
+        let mut positions = vec![*target];
+        let mut cur_pos = *target;
+
+        for _i in 0 .. jumps {
+            let next = candidates.iter()
+                .filter(|pos| !positions.contains(pos))
+                .filter(|pos| cur_pos.distance_to(pos) as usize <= reach)
+                .min_by_key(|pos| cur_pos.distance_to(pos));
+
+            match next {
+                Some(pos)   => {
+                    positions.push(*pos);
+                    cur_pos = *pos;
+                },
+                None        => break,
+            }
+        }
+
+        positions
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::context::ContextBuilder;
+
+    #[test]
+    fn shapes() {
+        let ctx = ContextBuilder::default().grid_radius(5).build();
+        let origin = coords::Position::default();
+        let target = coords::Position::new(2, 0, -2, &ctx).unwrap();
+        let facing = hex_directions::Side::NorthEast;
+
+        // Single and blast
+        assert_eq!(Shape::Single.resolve(&origin, &target, facing, &[], &ctx), vec![target]);
+        assert_eq!(Shape::Blast(1).resolve(&origin, &target, facing, &[], &ctx).len(), 7);
+        assert_eq!(Shape::Ring(2).resolve(&origin, &target, facing, &[], &ctx).len(), 12);
+
+        // Blast is clipped to the edge of the grid
+        let edge = coords::Position::new(5, 0, -5, &ctx).unwrap();
+        assert_eq!(Shape::Blast(1).resolve(&origin, &edge, facing, &[], &ctx).len(), 4);
+
+        // Line stops at the edge of the grid
+        let line = Shape::Line(10).resolve(&origin, &target, facing, &[], &ctx);
+        assert_eq!(line.len(), 5);
+        assert_eq!(line.last(), Some(&edge));
+
+        // Cone widens with distance from the caster
+        let cone = Shape::Cone(2).resolve(&origin, &target, facing, &[], &ctx);
+        assert_eq!(cone.len(), 1 + 3);
+        assert!(cone.contains(&coords::Position::new(1, 0, -1, &ctx).unwrap()));
+        assert!(!cone.contains(&coords::Position::new(0, 1, -1, &ctx).unwrap()));
+
+        // Chain jumps to the nearest candidate within reach
+        let near = coords::Position::new(3, 0, -3, &ctx).unwrap();
+        let far = coords::Position::new(-3, 0, 3, &ctx).unwrap();
+        let chain = Shape::Chain(3, 2).resolve(&origin, &target, facing, &[far, near], &ctx);
+        assert_eq!(chain, vec![target, near]);
+    }
+}
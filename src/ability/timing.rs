@@ -0,0 +1,271 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ability/timing.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the real-time characteristics of CastIron abilities.
+
+    Each ability has a cooldown between uses, a number of charges that
+    recharge over time, a cast time, and a channelled duration. All times are
+    measured in seconds against the game clock, and runtime state is
+    evaluated lazily at whatever tick it is queried.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Timing configuration of an ability
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+#[serde(default)]
+pub struct Timing {
+    pub cooldown:       f64,    // Minimum time between uses
+    pub max_charges:    u8,     // Number of uses that may be banked
+    pub recharge:       f64,    // Time to regain a single charge
+    pub cast_time:      f64,    // Time spent casting before the ability takes effect
+    pub channel:        f64,    // Time spent channelling after the ability takes effect
+}
+
+/// Runtime cooldown and charge state of an ability
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Cooldown {
+    charges:        u8,     // Charges available as of recharge_start
+    recharge_start: f64,    // Tick at which the current recharge began
+    ready_at:       f64,    // Tick at which the cooldown expires
+}
+
+/// Enumeration of an ability's readiness, along with the time remaining until it is ready
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Readiness {
+    Ready,
+    Cooldown(f64),
+    Recharging(f64),
+    Busy(f64),
+}
+
+/// Record of an actor's most recent cast, from which its current activity is derived
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct ActiveCast {
+    pub ability:    [u8; 16],
+    pub started:    f64,
+    pub cast_time:  f64,
+    pub channel:    f64,
+}
+
+/// Enumeration of the activities an actor may be engaged in at a given tick
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Activity {
+    Idle,
+    Casting([u8; 16], f64),     // Ability being cast, and tick at which casting completes
+    Channeling([u8; 16], f64),  // Ability being channelled, and tick at which channelling ends
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Timing {
+    /// Fully-qualified constructor. Every ability banks at least one charge, so a max_charges
+    /// of zero is raised to one.
+    pub fn new(cooldown: f64, max_charges: u8, recharge: f64, cast_time: f64, channel: f64) -> Self {
+        Self {cooldown, max_charges: u8::max(max_charges, 1), recharge, cast_time, channel}
+    }
+}
+
+impl Cooldown {
+    /// Constructs a fresh cooldown state with all charges available
+    pub fn new(timing: &Timing) -> Self {
+        Self {
+            charges:        timing.max_charges,
+            recharge_start: 0.0,
+            ready_at:       0.0,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Determines the number of charges available at the given tick
+    pub fn charges(&self, timing: &Timing, tick: f64) -> u8 {
+        self.recharged(timing, tick).0
+    }
+
+    /// Determines the readiness of the ability at the given tick
+    pub fn readiness(&self, timing: &Timing, tick: f64) -> Readiness {
+        if tick < self.ready_at {
+            return Readiness::Cooldown(self.ready_at - tick);
+        }
+
+        let (charges, recharge_start) = self.recharged(timing, tick);
+        if charges == 0 {
+            return Readiness::Recharging(recharge_start + timing.recharge - tick);
+        }
+
+        Readiness::Ready
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Spends a charge and begins the cooldown at the given tick.
+    /// Returns the readiness at that tick if the ability was not ready.
+    pub fn trigger(&mut self, timing: &Timing, tick: f64) -> Result<(), Readiness> {
+        match self.readiness(timing, tick) {
+            Readiness::Ready    => {},
+            not_ready           => return Err(not_ready),
+        }
+
+        let (charges, recharge_start) = self.recharged(timing, tick);
+
+        // Dropping below full charges starts a new recharge
+        self.recharge_start = if charges == timing.max_charges { tick } else { recharge_start };
+        self.charges = charges - 1;
+        self.ready_at = tick + timing.cooldown;
+
+        Ok(())
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Helper Methods    *
+    \*  *  *  *  *  *  *  */
+
+    /// Determines the charges available at the given tick, along with the tick at which the
+    /// in-progress recharge began.
+    fn recharged(&self, timing: &Timing, tick: f64) -> (u8, f64) {
+        if self.charges >= timing.max_charges || timing.recharge <= 0.0 {
+            return (timing.max_charges, tick);
+        }
+
+        let regained = ((tick - self.recharge_start) / timing.recharge).floor().max(0.0) as u64;
+        let missing = (timing.max_charges - self.charges) as u64;
+        if regained >= missing {
+            (timing.max_charges, tick)
+        } else {
+            (self.charges + regained as u8, self.recharge_start + regained as f64 * timing.recharge)
+        }
+    }
+}
+
+impl ActiveCast {
+    /// Determines the activity represented by this cast at the given tick
+    pub fn activity(&self, tick: f64) -> Activity {
+        let cast_end = self.started + self.cast_time;
+        let channel_end = cast_end + self.channel;
+
+        if tick < cast_end {
+            Activity::Casting(self.ability, cast_end)
+        }
+        else if tick < channel_end {
+            Activity::Channeling(self.ability, channel_end)
+        }
+        else {
+            Activity::Idle
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for Timing {
+    /// Instant, single-charge abilities with no cooldown
+    fn default() -> Self {
+        Self {
+            cooldown:       0.0,
+            max_charges:    1,
+            recharge:       0.0,
+            cast_time:      0.0,
+            channel:        0.0,
+        }
+    }
+}
+impl Default for Cooldown {
+    fn default() -> Self {
+        Self::new(&Timing::default())
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charges_and_cooldown() {
+        let timing = Timing::new(1.0, 2, 5.0, 0.0, 0.0);
+        let mut cooldown = Cooldown::new(&timing);
+
+        // Spend both charges, respecting the cooldown in between
+        assert_eq!(cooldown.trigger(&timing, 0.0), Ok(()));
+        assert_eq!(cooldown.trigger(&timing, 0.5), Err(Readiness::Cooldown(0.5)));
+        assert_eq!(cooldown.trigger(&timing, 1.0), Ok(()));
+        assert_eq!(cooldown.charges(&timing, 1.0), 0);
+
+        // First charge returns 5s after the first use
+        assert_eq!(cooldown.readiness(&timing, 4.0), Readiness::Recharging(1.0));
+        assert_eq!(cooldown.charges(&timing, 5.0), 1);
+        assert_eq!(cooldown.charges(&timing, 10.0), 2);
+
+        // Using a recharged charge does not reset the in-progress recharge
+        assert_eq!(cooldown.trigger(&timing, 6.0), Ok(()));
+        assert_eq!(cooldown.charges(&timing, 9.9), 0);
+        assert_eq!(cooldown.charges(&timing, 10.0), 1);
+
+        // Abilities without a charge to bank would never be usable
+        let timing = Timing::new(0.0, 0, 0.0, 0.0, 0.0);
+        assert_eq!(timing.max_charges, 1);
+        assert_eq!(Cooldown::new(&timing).trigger(&timing, 0.0), Ok(()));
+    }
+
+    #[test]
+    fn activity() {
+        let cast = ActiveCast {ability: [0; 16], started: 10.0, cast_time: 1.0, channel: 2.0};
+
+        assert_eq!(cast.activity(10.5), Activity::Casting([0; 16], 11.0));
+        assert_eq!(cast.activity(11.0), Activity::Channeling([0; 16], 13.0));
+        assert_eq!(cast.activity(13.0), Activity::Idle);
+    }
+}
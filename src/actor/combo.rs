@@ -0,0 +1,368 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : actor/combo.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines combos: sequences of abilities which, when used by a
+    single actor within a time or turn window, grant bonus effects.
+
+    Each actor tracks its own recent ability usage. Combo definitions are
+    plain data, collected into a book which may be loaded from JSON. A combo
+    is completed by the final ability in its sequence, after which the
+    actor's history is cleared so that combos do not chain off one another.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use crate::{
+    ability::{
+        Ability,
+        aspect::School,
+    },
+    actor::status::Affliction,
+    element::Element,
+};
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Maximum number of ability uses remembered by a tracker
+const MAX_COMBO_HISTORY: usize = 8;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the ways a single step of a combo may be matched
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Step {
+    School(School),
+    Element(Element),
+    Ability([u8; 16]),
+}
+
+/// Enumeration of the windows within which a combo must be completed
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Window {
+    Seconds(f64),   // All steps used within the given span of game time
+    Turns(u32),     // All steps used within the given number of turns
+}
+
+/// Enumeration of the bonuses granted upon completing a combo
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Bonus {
+    Potency(f64),           // Potency of the finishing ability is multiplied by the given factor
+    Damage(usize),          // Additional damage dealt to each actor affected by the finishing ability
+    Heal(usize),            // Health restored to the caster
+    Afflict(Affliction),    // Status inflicted upon each actor affected by the finishing ability
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Combo {
+    pub name:       String,
+    pub steps:      Vec<Step>,
+    pub window:     Window,
+    pub bonuses:    Vec<Bonus>,
+}
+
+/// Collection of combo definitions in effect
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct ComboBook {
+    combos: Vec<Combo>,
+}
+
+/// Record of a single ability use
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Usage {
+    pub ability:    [u8; 16],
+    pub school:     School,
+    pub element:    Element,
+    pub tick:       f64,
+    pub turn:       u32,
+}
+
+/// Per-actor record of recent ability usage
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Tracker {
+    history:    Vec<Usage>,
+    turn:       u32,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Step {
+    /// Determines if the given usage satisfies this step
+    pub fn matches(&self, usage: &Usage) -> bool {
+        match *self {
+            Step::School(school)    => usage.school == school,
+            Step::Element(element)  => usage.element == element,
+            Step::Ability(uid)      => usage.ability == uid,
+        }
+    }
+}
+
+impl Window {
+    /// Determines if the span between the two usages falls within the window
+    pub fn contains(&self, first: &Usage, last: &Usage) -> bool {
+        match *self {
+            Window::Seconds(secs)   => last.tick - first.tick <= secs,
+            Window::Turns(turns)    => last.turn.saturating_sub(first.turn) < turns,
+        }
+    }
+}
+
+impl Combo {
+    /// Fully-qualified constructor
+    pub fn new(name: &str, steps: Vec<Step>, window: Window, bonuses: Vec<Bonus>) -> Self {
+        Self {
+            name: name.to_string(),
+            steps,
+            window,
+            bonuses,
+        }
+    }
+
+    /// Determines if the given usage, following the given history, completes the combo
+    pub fn is_completed_by(&self, history: &[Usage], latest: &Usage) -> bool {
+        if self.steps.is_empty() || self.steps.len() > history.len() + 1 {
+            return false;
+        }
+
+        let sequence: Vec<&Usage> = history[history.len() + 1 - self.steps.len()..].iter()
+            .chain(std::iter::once(latest))
+            .collect();
+
+        self.steps.iter().zip(sequence.iter()).all(|(step, usage)| step.matches(usage))
+            && self.window.contains(sequence[0], latest)
+    }
+}
+
+impl ComboBook {
+    /// Constructs an empty combo book
+    pub fn new() -> Self {
+        Self {
+            combos: Vec::new(),
+        }
+    }
+
+    /// Constructs a combo book from the given JSON
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn combos(&self) -> &Vec<Combo> {
+        &self.combos
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn add_combo(&mut self, combo: Combo) {
+        self.combos.push(combo);
+    }
+
+    pub fn clear(&mut self) {
+        self.combos.clear();
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns all combos completed by the given usage, following the tracker's history
+    pub fn completed_by(&self, tracker: &Tracker, latest: &Usage) -> Vec<&Combo> {
+        self.combos.iter()
+            .filter(|combo| combo.is_completed_by(&tracker.history, latest))
+            .collect()
+    }
+}
+
+impl Usage {
+    /// Records a use of the given ability at the given tick and turn
+    pub fn new(ability: &Ability, tick: f64, turn: u32) -> Self {
+        Self {
+            ability:    *ability.uid(),
+            school:     *ability.school(),
+            element:    *ability.element(),
+            tick,
+            turn,
+        }
+    }
+}
+
+impl Tracker {
+    /// Constructs a tracker with no history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn history(&self) -> &Vec<Usage> {
+        &self.history
+    }
+
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Records the given usage. Completing a combo consumes the history.
+    pub fn record(&mut self, usage: Usage, completed_combo: bool) {
+        if completed_combo {
+            self.history.clear();
+            return;
+        }
+
+        self.history.push(usage);
+        if self.history.len() > MAX_COMBO_HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    /// Advances the tracker's turn counter
+    pub fn end_turn(&mut self) {
+        self.turn += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for ComboBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(school: School, element: Element, tick: f64, turn: u32) -> Usage {
+        Usage {ability: [0; 16], school, element, tick, turn}
+    }
+
+    #[test]
+    fn time_window() {
+        let book = ComboBook::from_json(r#"{"combos": [{
+            "name": "Steam Burst",
+            "steps": [{"Element": "Water"}, {"Element": "Fire"}],
+            "window": {"Seconds": 3.0},
+            "bonuses": [{"Damage": 10}]
+        }]}"#).unwrap();
+
+        let mut tracker = Tracker::new();
+        tracker.record(usage(School::Unset, Element::Water, 0.0, 0), false);
+
+        // Too slow
+        let late = usage(School::Unset, Element::Fire, 5.0, 0);
+        assert!(book.completed_by(&tracker, &late).is_empty());
+
+        let fire = usage(School::Unset, Element::Fire, 2.0, 0);
+        let completed = book.completed_by(&tracker, &fire);
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].bonuses, vec![Bonus::Damage(10)]);
+
+        // Completion consumes the history
+        tracker.record(fire, true);
+        assert!(tracker.history().is_empty());
+    }
+
+    #[test]
+    fn turn_window() {
+        let combo = Combo::new("Unmaking",
+                               vec![Step::School(School::Alteration), Step::School(School::Destruction)],
+                               Window::Turns(2),
+                               vec![Bonus::Potency(1.5)]);
+
+        let mut tracker = Tracker::new();
+        tracker.record(usage(School::Alteration, Element::Unset, 0.0, tracker.turn()), false);
+        tracker.end_turn();
+        assert!(combo.is_completed_by(tracker.history(), &usage(School::Destruction, Element::Unset, 0.0, tracker.turn())));
+
+        tracker.end_turn();
+        assert!(!combo.is_completed_by(tracker.history(), &usage(School::Destruction, Element::Unset, 0.0, tracker.turn())));
+    }
+}
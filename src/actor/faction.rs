@@ -0,0 +1,283 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : actor/faction.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines factions and the relationships between them.
+
+    Actors may belong to a single faction, named by a string. Members of the
+    same faction are always allied, while relationships between factions are
+    looked up in a symmetric matrix. Actors belonging to no faction are
+    neutral towards everyone but themselves.
+
+    Evil abilities may not be cast on allies, unless the caster's faction is
+    permitted to do so.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    error::Error,
+    fmt,
+};
+
+use crate::{
+    ability::{
+        Ability,
+        aspect::Morality,
+    },
+    world::World,
+    Plottable,
+};
+
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the relationships between factions
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Hash,
+    Serialize, Deserialize
+)]
+pub enum Relationship {
+    Allied,
+    Neutral,
+    Hostile,
+}
+
+/// Symmetric matrix of relationships between factions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relations {
+    entries:    HashMap<(String, String), Relationship>,
+    ruthless:   HashSet<String>,    // Factions permitted to cast evil abilities on allies
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TargetingError {
+    UnknownActor([u8; 16]),
+    EvilAgainstAlly([u8; 16]),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Relations {
+    /// Constructs an empty matrix, under which distinct factions are neutral
+    pub fn new() -> Self {
+        Self {
+            entries:    HashMap::new(),
+            ruthless:   HashSet::new(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the relationship between the two given factions
+    pub fn get(&self, a: &str, b: &str) -> Relationship {
+        if a == b {
+            return Relationship::Allied;
+        }
+
+        self.entries.get(&key(a, b)).cloned().unwrap_or(Relationship::Neutral)
+    }
+
+    /// Determines if the given faction is permitted to cast evil abilities on its allies
+    pub fn is_ruthless(&self, faction: &str) -> bool {
+        self.ruthless.contains(faction)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the relationship between the two given factions, in both directions
+    pub fn set(&mut self, a: &str, b: &str, relationship: Relationship) {
+        self.entries.insert(key(a, b), relationship);
+    }
+
+    pub fn set_ruthless(&mut self, faction: &str, ruthless: bool) {
+        if ruthless {
+            self.ruthless.insert(faction.to_string());
+        } else {
+            self.ruthless.remove(faction);
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Determines the relationship between the two given actors.
+/// Actors are allied with themselves, and neutral to all others if either belongs to no faction.
+pub fn relationship(world: &World, a: &[u8; 16], b: &[u8; 16]) -> Relationship {
+    if a == b {
+        return Relationship::Allied;
+    }
+
+    let factions = (world.actor(a).and_then(|actor| actor.faction()),
+                    world.actor(b).and_then(|actor| actor.faction()));
+    match factions {
+        (Some(a), Some(b))  => world.relations().get(a, b),
+        _                   => Relationship::Neutral,
+    }
+}
+
+/// Returns the UIDs of all undefeated actors hostile to the given actor within the given distance of it
+pub fn hostiles_within(world: &World, actor_uid: &[u8; 16], distance: usize) -> Vec<[u8; 16]> {
+    let origin = match world.actor(actor_uid) {
+        Some(actor) => *actor.origin(),
+        None        => return Vec::new(),
+    };
+
+    world.actors().iter()
+        .filter(|other| !other.is_defeated())
+        .filter(|other| origin.distance_to(other.origin()) as usize <= distance)
+        .filter(|other| relationship(world, actor_uid, other.uid()) == Relationship::Hostile)
+        .map(|other| *other.uid())
+        .collect()
+}
+
+/// Determines if the given caster may target the given actor with the given ability
+pub fn can_target(world:        &World,
+                  caster_uid:   &[u8; 16],
+                  ability:      &Ability,
+                  target_uid:   &[u8; 16]) -> Result<(), TargetingError> {
+    let caster = world.actor(caster_uid).ok_or(TargetingError::UnknownActor(*caster_uid))?;
+    if world.actor(target_uid).is_none() {
+        return Err(TargetingError::UnknownActor(*target_uid));
+    }
+
+    let ruthless = caster.faction().is_some_and(|faction| world.relations().is_ruthless(faction));
+    if *ability.morality() == Morality::Evil &&
+       !ruthless &&
+       relationship(world, caster_uid, target_uid) == Relationship::Allied {
+        return Err(TargetingError::EvilAgainstAlly(*target_uid));
+    }
+
+    Ok(())
+}
+
+/// Normalizes an unordered pair of factions into a matrix key
+fn key(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for Relations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *   TargetingError   *
+\*  *  *  *  *  *  *  */
+impl Error for TargetingError {}
+impl fmt::Display for TargetingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TargetingError::UnknownActor(uid)       => {
+                write!(f, "Unknown actor {}", Uuid::from_bytes(*uid))
+            },
+            TargetingError::EvilAgainstAlly(uid)    => {
+                write!(f, "Evil abilities may not be cast on ally {}", Uuid::from_bytes(*uid))
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ability::aspect::*,
+        actor::Actor,
+        context::Context,
+        coords,
+        element::Element,
+    };
+
+    fn actor(world: &mut World, name: &'static str, x: i32, faction: Option<&str>) -> [u8; 16] {
+        let ctx = world.ctx().clone();
+        let mut actor = Actor::new(name, coords::Position::new(x, 0, -x, &ctx).unwrap(), 0, Vec::new());
+        actor.set_faction(faction);
+        let uid = *actor.uid();
+        world.add_actor(actor);
+
+        uid
+    }
+
+    #[test]
+    fn relationships_and_targeting() {
+        let mut world = World::new(Context::default());
+        let knight = actor(&mut world, "Knight", 0, Some("Crown"));
+        let squire = actor(&mut world, "Squire", 1, Some("Crown"));
+        let bandit = actor(&mut world, "Bandit", 2, Some("Bandits"));
+        let far_bandit = actor(&mut world, "Far Bandit", 5, Some("Bandits"));
+        let hermit = actor(&mut world, "Hermit", 3, None);
+
+        let mut relations = Relations::new();
+        relations.set("Crown", "Bandits", Relationship::Hostile);
+        world.set_relations(relations);
+
+        assert_eq!(relationship(&world, &knight, &squire), Relationship::Allied);
+        assert_eq!(relationship(&world, &bandit, &knight), Relationship::Hostile);
+        assert_eq!(relationship(&world, &knight, &hermit), Relationship::Neutral);
+        assert_eq!(hostiles_within(&world, &knight, 3), vec![bandit]);
+        assert_eq!(hostiles_within(&world, &knight, 5), vec![bandit, far_bandit]);
+
+        let curse = Ability::new("Curse", 10, Aspects::new(Aesthetics::Ugly, Element::Dark, Method::Vocal, Morality::Evil, School::Destruction));
+        assert_eq!(can_target(&world, &knight, &curse, &squire), Err(TargetingError::EvilAgainstAlly(squire)));
+        assert_eq!(can_target(&world, &knight, &curse, &bandit), Ok(()));
+
+        let mut relations = world.relations().clone();
+        relations.set_ruthless("Crown", true);
+        world.set_relations(relations);
+        assert_eq!(can_target(&world, &knight, &curse, &squire), Ok(()));
+    }
+}
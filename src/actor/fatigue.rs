@@ -0,0 +1,260 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : actor/fatigue.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the fatigue economy of CastIron actors.
+
+    Using an ability adds fatigue based on its potency and aspects, and fatigue
+    recovers over game time. As fatigue crosses defined thresholds, the actor
+    becomes progressively exhausted and suffers penalties.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    error::Error,
+    fmt,
+};
+
+use crate::ability::{
+    Ability,
+    aspect::*,
+};
+
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Amount of potency that equates to a single point of fatigue
+const POTENCY_PER_FATIGUE_POINT:    usize = 10;
+
+/// Points of fatigue recovered per second of game time
+const FATIGUE_RECOVERY_PER_SEC: f64 = 4.0;
+
+// Exhaustion thresholds
+const FRESH_FATIGUE_RANGE_MIN:      u8 = 0;
+const FRESH_FATIGUE_RANGE_MAX:      u8 = 63;
+const WINDED_FATIGUE_RANGE_MIN:     u8 = 64;
+const WINDED_FATIGUE_RANGE_MAX:     u8 = 127;
+const TIRED_FATIGUE_RANGE_MIN:      u8 = 128;
+const TIRED_FATIGUE_RANGE_MAX:      u8 = 191;
+const EXHAUSTED_FATIGUE_RANGE_MIN:  u8 = 192;
+const EXHAUSTED_FATIGUE_RANGE_MAX:  u8 = 254;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the exhaustion states an actor passes through as fatigue accumulates
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Default)]
+pub enum Exhaustion {
+    #[default]
+    Fresh,
+    Winded,
+    Tired,
+    Exhausted,
+    Collapsed,
+}
+
+#[derive(Debug)]
+pub enum FatigueError {
+    Overflow(u8, usize),
+    UnknownAbility([u8; 16]),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Calculates the fatigue incurred by using the given ability, unless the ability specifies its own.
+/// Returned as a usize so that callers may detect costs that would overflow an actor's fatigue.
+pub fn cost(ability: &Ability) -> usize {
+    if let Some(cost) = ability.fatigue_cost() {
+        return cost;
+    }
+
+    let potency_cost = ability.potency() / POTENCY_PER_FATIGUE_POINT;
+
+    potency_cost.saturating_add(aspect_cost(ability.aspects()))
+}
+
+/// Calculates the flat fatigue surcharge imposed by the given aspects
+pub fn aspect_cost(aspects: &Aspects) -> usize {
+    let aesthetics_cost = match aspects.aesthetics {
+        Aesthetics::Unset       => 0,
+        Aesthetics::Beautiful   => 1,
+        Aesthetics::Impressive  => 2,
+        Aesthetics::Erotic      => 1,
+        Aesthetics::Ugly        => 0,
+        Aesthetics::Subtle      => 2,
+    };
+
+    let method_cost = match aspects.method {
+        Method::Unset   => 0,
+        Method::Staff   => 1,
+        Method::Wand    => 1,
+        Method::Manual  => 2,
+        Method::Vocal   => 3,
+    };
+
+    let school_cost = match aspects.school {
+        School::Unset       => 0,
+        School::Destruction => 3,
+        School::Restoration => 2,
+        School::Conjuration => 4,
+        School::Alteration  => 2,
+        School::Illusion    => 1,
+        School::Nature      => 2,
+        School::Song        => 1,
+    };
+
+    aesthetics_cost + method_cost + school_cost
+}
+
+/// Calculates the (whole) points of fatigue recovered over the given number of seconds plus any
+/// carried-over fraction, along with the fractional remainder to be carried into the next recovery.
+pub fn recovery(elapsed_secs: f64, carry: f64) -> (u8, f64) {
+    let total = elapsed_secs.max(0.0) * FATIGUE_RECOVERY_PER_SEC + carry;
+    let whole = total.floor();
+
+    if whole >= u8::MAX as f64 {
+        (u8::MAX, 0.0)
+    } else {
+        (whole as u8, total - whole)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Exhaustion {
+    /// Multiplier applied to the potency of abilities used while in this state
+    pub fn potency_modifier(&self) -> f64 {
+        match self {
+            Exhaustion::Fresh       => 1.00,
+            Exhaustion::Winded      => 0.90,
+            Exhaustion::Tired       => 0.75,
+            Exhaustion::Exhausted   => 0.50,
+            Exhaustion::Collapsed   => 0.00,
+        }
+    }
+
+    /// Additional movement cost (per cell) imposed while in this state
+    pub fn movement_penalty(&self) -> usize {
+        match self {
+            Exhaustion::Fresh       => 0,
+            Exhaustion::Winded      => 0,
+            Exhaustion::Tired       => 1,
+            Exhaustion::Exhausted   => 2,
+            Exhaustion::Collapsed   => usize::MAX,
+        }
+    }
+
+    /// Determines if an actor in this state is able to take any action at all
+    pub fn can_act(&self) -> bool {
+        *self != Exhaustion::Collapsed
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+/*  *  *  *  *  *  *  *\
+ *     Exhaustion     *
+\*  *  *  *  *  *  *  */
+impl From<u8> for Exhaustion {
+    fn from(src: u8) -> Self {
+        match src {
+            FRESH_FATIGUE_RANGE_MIN     ..= FRESH_FATIGUE_RANGE_MAX     => Exhaustion::Fresh,
+            WINDED_FATIGUE_RANGE_MIN    ..= WINDED_FATIGUE_RANGE_MAX    => Exhaustion::Winded,
+            TIRED_FATIGUE_RANGE_MIN     ..= TIRED_FATIGUE_RANGE_MAX     => Exhaustion::Tired,
+            EXHAUSTED_FATIGUE_RANGE_MIN ..= EXHAUSTED_FATIGUE_RANGE_MAX => Exhaustion::Exhausted,
+            _                                                           => Exhaustion::Collapsed,
+        }
+    }
+}
+impl From<Exhaustion> for String {
+    fn from(src: Exhaustion) -> Self {
+        match src {
+            Exhaustion::Fresh       => Self::from("Fresh"),
+            Exhaustion::Winded      => Self::from("Winded"),
+            Exhaustion::Tired       => Self::from("Tired"),
+            Exhaustion::Exhausted   => Self::from("Exhausted"),
+            Exhaustion::Collapsed   => Self::from("Collapsed"),
+        }
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *    FatigueError    *
+\*  *  *  *  *  *  *  */
+impl Error for FatigueError {}
+impl fmt::Display for FatigueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FatigueError::Overflow(cur, cost)   => {
+                write!(f, "Fatigue cost {} would overflow current fatigue {}", cost, cur)
+            },
+            FatigueError::UnknownAbility(uid)   => {
+                write!(f, "Actor does not know ability {}", Uuid::from_bytes(*uid))
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds() {
+        assert_eq!(Exhaustion::from(0),     Exhaustion::Fresh);
+        assert_eq!(Exhaustion::from(63),    Exhaustion::Fresh);
+        assert_eq!(Exhaustion::from(64),    Exhaustion::Winded);
+        assert_eq!(Exhaustion::from(128),   Exhaustion::Tired);
+        assert_eq!(Exhaustion::from(192),   Exhaustion::Exhausted);
+        assert_eq!(Exhaustion::from(254),   Exhaustion::Exhausted);
+        assert_eq!(Exhaustion::from(255),   Exhaustion::Collapsed);
+    }
+
+    #[test]
+    fn recovery_carries_remainder() {
+        let (whole, remainder) = recovery(0.1, 0.0);
+        assert_eq!(whole, 0);
+        assert!((remainder - 0.4).abs() < 1e-9);
+
+        let (whole, remainder) = recovery(0.15, remainder);
+        assert_eq!(whole, 1);
+        assert!(remainder < 1e-9);
+
+        let (whole, _) = recovery(1000.0, 0.0);
+        assert_eq!(whole, u8::MAX);
+    }
+}
@@ -0,0 +1,362 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : actor/inventory.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the inventories in which actors carry elemental
+    essences.
+
+    Actors harvest essences from the resources covering them, drawing the
+    resource down by one state and gaining essence of its element in
+    proportion to the state it was drawn from. Inventories hold a limited
+    total of essence, and any harvest beyond that limit is lost.
+
+    Essences are spent as the reagents some abilities require, and may be
+    infused into abilities of the same element to empower them.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    error::Error,
+    fmt,
+};
+
+use crate::{
+    element::{
+        Element,
+        Elemental,
+    },
+    world::World,
+    Plottable,
+};
+
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default total amount of essence an inventory may hold
+const DEFAULT_CAPACITY: u32 = 20;
+
+/// Bonus potency granted to an empowered ability for each essence infused into it
+const POTENCY_BONUS_PER_ESSENCE: f64 = 0.05;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// An amount of essence of a single element
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Essence {
+    pub element:    Element,
+    pub amount:     u32,
+}
+
+/// Collection of the essences carried by an actor
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Inventory {
+    capacity:   u32,            // Total amount of essence the inventory may hold
+    essences:   Vec<Essence>,   // Essences held, at most one entry per element
+}
+
+#[derive(Debug)]
+pub enum InventoryError {
+    Full(u32),
+    Insufficient(Element, u32, u32),    // Element, amount required, amount held
+    UnknownActor([u8; 16]),
+    UnknownResource([u8; 16]),
+    OutOfReach([u8; 16]),
+    Depleted([u8; 16]),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Essence {
+    pub fn new(element: Element, amount: u32) -> Self {
+        Self {element, amount}
+    }
+}
+
+impl Inventory {
+    /// Constructs an empty inventory holding at most the given total amount of essence
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            essences:   Vec::new(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn essences(&self) -> &Vec<Essence> {
+        &self.essences
+    }
+
+    /// Returns the amount of essence of the given element held
+    pub fn amount(&self, element: Element) -> u32 {
+        self.essences.iter()
+            .find(|essence| essence.element == element)
+            .map_or(0, |essence| essence.amount)
+    }
+
+    /// Returns the total amount of essence held, across all elements
+    pub fn total(&self) -> u32 {
+        self.essences.iter().map(|essence| essence.amount).sum()
+    }
+
+    /// Returns the amount of essence the inventory has room for
+    pub fn free(&self) -> u32 {
+        self.capacity.saturating_sub(self.total())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.essences.is_empty()
+    }
+
+    /// Determines if all of the given essences are held, returning the first shortfall if not
+    pub fn can_spend(&self, essences: &[Essence]) -> Result<(), InventoryError> {
+        let mut elements: Vec<Element> = essences.iter().map(|essence| essence.element).collect();
+        elements.sort();
+        elements.dedup();
+
+        for element in elements {
+            let required: u32 = essences.iter()
+                .filter(|essence| essence.element == element)
+                .map(|essence| essence.amount)
+                .sum();
+            let held = self.amount(element);
+            if held < required {
+                return Err(InventoryError::Insufficient(element, required, held));
+            }
+        }
+
+        Ok(())
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the capacity of the inventory. Essences already held beyond it are kept.
+    pub fn set_capacity(&mut self, capacity: u32) {
+        self.capacity = capacity;
+    }
+
+    /// Stores as much of the given essence as there is room for.
+    /// Returns the amount actually stored.
+    pub fn store(&mut self, essence: Essence) -> u32 {
+        let stored = u32::min(essence.amount, self.free());
+        if stored == 0 || essence.element == Element::Unset {
+            return 0;
+        }
+
+        match self.essences.iter_mut().find(|held| held.element == essence.element) {
+            Some(held)  => held.amount += stored,
+            None        => self.essences.push(Essence::new(essence.element, stored)),
+        }
+
+        stored
+    }
+
+    /// Spends all of the given essences, or none of them if any are lacking
+    pub fn spend(&mut self, essences: &[Essence]) -> Result<(), InventoryError> {
+        self.can_spend(essences)?;
+
+        for essence in essences {
+            if let Some(held) = self.essences.iter_mut().find(|held| held.element == essence.element) {
+                held.amount -= essence.amount;
+            }
+        }
+        self.essences.retain(|held| held.amount > 0);
+
+        Ok(())
+    }
+
+    /// Spends up to the given amount of essence of the given element.
+    /// Returns the amount actually spent.
+    pub fn draw(&mut self, element: Element, limit: u32) -> u32 {
+        let drawn = u32::min(limit, self.amount(element));
+        if drawn > 0 {
+            // Cannot fail, having just checked the amount held
+            let _res = self.spend(&[Essence::new(element, drawn)]);
+        }
+
+        drawn
+    }
+
+    pub fn clear(&mut self) {
+        self.essences.clear();
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Harvests essence from the given resource into the inventory of the given actor, drawing the
+/// resource down by one state. The actor must stand within the resource's radius, and must have
+/// room for at least some essence. Returns the amount of essence stored.
+pub fn harvest(world: &mut World, actor_uid: &[u8; 16], resource_uid: &[u8; 16]) -> Result<u32, InventoryError> {
+    let actor = world.actor(actor_uid).ok_or(InventoryError::UnknownActor(*actor_uid))?;
+    let resource = world.resource(resource_uid).ok_or(InventoryError::UnknownResource(*resource_uid))?;
+
+    if !resource.covers(actor.origin()) {
+        return Err(InventoryError::OutOfReach(*resource_uid));
+    }
+    if actor.inventory().free() == 0 {
+        return Err(InventoryError::Full(actor.inventory().capacity()));
+    }
+
+    let element = resource.element();
+    let (_drained, magnitude) = world.consume_resource(resource_uid).ok_or(InventoryError::Depleted(*resource_uid))?;
+
+    let actor = world.actor_mut(actor_uid).ok_or(InventoryError::UnknownActor(*actor_uid))?;
+    Ok(actor.inventory_mut().store(Essence::new(element, magnitude as u32)))
+}
+
+/// Multiplier applied to the potency of an ability infused with the given amount of essence
+pub fn potency_multiplier(infused: u32) -> f64 {
+    1.0 + infused as f64 * POTENCY_BONUS_PER_ESSENCE
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+/*  *  *  *  *  *  *  *\
+ *     Inventory      *
+\*  *  *  *  *  *  *  */
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *   InventoryError   *
+\*  *  *  *  *  *  *  */
+impl Error for InventoryError {}
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InventoryError::Full(capacity)                      => {
+                write!(f, "Inventory is full, holding {} essence", capacity)
+            },
+            InventoryError::Insufficient(element, needed, held) => {
+                write!(f, "Requires {} {:?} essence, but only {} held", needed, element, held)
+            },
+            InventoryError::UnknownActor(uid)                   => {
+                write!(f, "Unknown actor {}", Uuid::from_bytes(*uid))
+            },
+            InventoryError::UnknownResource(uid)                => {
+                write!(f, "Unknown resource {}", Uuid::from_bytes(*uid))
+            },
+            InventoryError::OutOfReach(uid)                     => {
+                write!(f, "Resource {} is out of reach", Uuid::from_bytes(*uid))
+            },
+            InventoryError::Depleted(uid)                       => {
+                write!(f, "Resource {} is depleted", Uuid::from_bytes(*uid))
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        actor::Actor,
+        context::Context,
+        coords::tests::pos,
+        mechanics::resource::{
+            Resource,
+            State,
+        },
+    };
+
+    #[test]
+    fn harvesting_and_spending() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        let mut druid = Actor::new("Druid", pos(0, 0, 0, &ctx), 0, Vec::new());
+        druid.inventory_mut().set_capacity(6);
+        let druid_uid = *druid.uid();
+        world.add_actor(druid);
+
+        let grove = Resource::new(Element::Earth, State::Full, pos(1, 0, -1, &ctx), 2);
+        let grove_uid = *grove.uid();
+        let distant = Resource::new(Element::Water, State::Full, pos(8, 0, -8, &ctx), 1);
+        let distant_uid = *distant.uid();
+        world.add_resource(grove);
+        world.add_resource(distant);
+
+        // Harvests fill the inventory up to its capacity, and the excess is lost
+        assert_eq!(harvest(&mut world, &druid_uid, &grove_uid).unwrap(), 4);
+        assert_eq!(harvest(&mut world, &druid_uid, &grove_uid).unwrap(), 2);
+        assert_eq!(world.resource(&grove_uid).unwrap().state(), State::Partial);
+        assert!(matches!(harvest(&mut world, &druid_uid, &grove_uid), Err(InventoryError::Full(6))));
+        assert!(matches!(harvest(&mut world, &druid_uid, &distant_uid), Err(InventoryError::OutOfReach(_))));
+
+        // Spending is all or nothing
+        let inventory = world.actor_mut(&druid_uid).unwrap().inventory_mut();
+        assert_eq!(inventory.amount(Element::Earth), 6);
+        let reagents = [Essence::new(Element::Earth, 2), Essence::new(Element::Fire, 1)];
+        assert!(matches!(inventory.spend(&reagents), Err(InventoryError::Insufficient(Element::Fire, 1, 0))));
+        assert_eq!(inventory.total(), 6);
+        inventory.spend(&reagents[..1]).unwrap();
+        assert_eq!(inventory.draw(Element::Earth, 10), 4);
+        assert!(inventory.is_empty());
+
+        // Inventory contents survive serialization
+        inventory.store(Essence::new(Element::Light, 3));
+        let json = serde_json::to_string(inventory).unwrap();
+        assert_eq!(serde_json::from_str::<Inventory>(&json).unwrap(), *inventory);
+    }
+}
@@ -0,0 +1,578 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : actor/mod.rs
+
+Copyright (C) 2017 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the Actor object. All PCs and NPCs will have an
+    associated Actor object to record their abilities and game state.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    fmt,
+    time::Duration,
+};
+
+use crate::{
+    ability::{
+        Ability,
+        aspect::Morality,
+        timing::{
+            ActiveCast,
+            Activity,
+            Readiness,
+        },
+    },
+    context::Context,
+    coords,
+    element::interaction::{
+        Matrix,
+        Reaction,
+    },
+    hex_directions,
+    Plottable,
+    Randomizable,
+};
+
+use rand::{
+    Rng,
+    distributions::Alphanumeric,
+};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Module Declarations
+///////////////////////////////////////////////////////////////////////////////
+
+pub mod combo;
+use self::combo::{
+    Tracker,
+    Usage,
+};
+pub mod faction;
+pub mod fatigue;
+use self::fatigue::{
+    Exhaustion,
+    FatigueError,
+};
+pub mod inventory;
+use self::inventory::Inventory;
+pub mod status;
+use self::status::{
+    Kind,
+    StatusEffect,
+    StatusTick,
+};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default maximum health of an actor
+const DEFAULT_MAX_HEALTH: usize = 100;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Struct containing state information for the Actor
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Actor {
+    uid:            [u8; 16],
+    name:           String,             // Actor's name
+    pos:            coords::Position,   // Actor's 3D position in the environment
+    cur_health:     usize,              // Actor's current health
+    max_health:     usize,              // Actor's maximum health
+    alignment:      Morality,           // Actor's moral alignment
+    silenced:       bool,               // Whether the actor is currently unable to speak
+    initiative:     i32,                // Actor's place in turn order, highest acting first
+    faction:        Option<String>,     // Faction the actor belongs to, if any
+    cur_fatigue:    u8,                 // Actor's current fatigue level
+    fatigue_carry:  f64,                // Fractional fatigue recovery carried between updates
+    abilities:      Vec<Ability>,       // List of Actor's Abilities
+    active_cast:    Option<ActiveCast>, // Actor's most recent cast, if it may still be in progress
+    statuses:       Vec<StatusEffect>,  // Status effects currently afflicting the Actor
+    combo:          Tracker,            // Actor's recent ability usage, for combo recognition
+    inventory:      Inventory,          // Elemental essences carried by the Actor
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Actor {
+    /// Fully-qualified constructor
+    pub fn new(name:        &'static str,
+               pos:         coords::Position,
+               cur_fatigue: u8,
+               abilities:   Vec<Ability>) -> Self {
+        Self {
+            uid:    *Uuid::new_v4().as_bytes(),
+            name:   name.to_string(),
+            pos,
+            cur_health:     DEFAULT_MAX_HEALTH,
+            max_health:     DEFAULT_MAX_HEALTH,
+            alignment:      Morality::default(),
+            silenced:       false,
+            initiative:     0,
+            faction:        None,
+            cur_fatigue,
+            fatigue_carry:  0.0,
+            abilities,
+            active_cast:    None,
+            statuses:       Vec::new(),
+            combo:          Tracker::new(),
+            inventory:      Inventory::default(),
+        }
+    }
+
+    /// Name-only constructor
+    pub fn new_name_only(name: &'static str) -> Self {
+        Self {
+            uid:            *Uuid::new_v4().as_bytes(),
+            name:           name.to_string(),
+            pos:            coords::Position::default(),
+            cur_health:     DEFAULT_MAX_HEALTH,
+            max_health:     DEFAULT_MAX_HEALTH,
+            alignment:      Morality::default(),
+            silenced:       false,
+            initiative:     0,
+            faction:        None,
+            cur_fatigue:    0,
+            fatigue_carry:  0.0,
+            abilities:      Vec::new(),
+            active_cast:    None,
+            statuses:       Vec::new(),
+            combo:          Tracker::new(),
+            inventory:      Inventory::default(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    // Returns a reference for the actor's unique ID
+    pub fn uid(&self) -> &[u8; 16] {
+        &self.uid
+    }
+
+    // Returns a reference for the actor's name
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    // Returns a reference for the actor's position
+    pub fn pos(&self) -> &coords::Position {
+        &self.pos
+    }
+
+    // Returns the actor's current health
+    pub fn cur_health(&self) -> usize {
+        self.cur_health
+    }
+
+    // Returns the actor's maximum health
+    pub fn max_health(&self) -> usize {
+        self.max_health
+    }
+
+    // Determines if the actor has been reduced to zero health
+    pub fn is_defeated(&self) -> bool {
+        self.cur_health == 0
+    }
+
+    // Returns the actor's moral alignment
+    pub fn alignment(&self) -> Morality {
+        self.alignment
+    }
+
+    // Determines if the actor is currently silenced
+    pub fn is_silenced(&self) -> bool {
+        self.silenced
+    }
+
+    // Returns the actor's initiative
+    pub fn initiative(&self) -> i32 {
+        self.initiative
+    }
+
+    // Returns the name of the faction the actor belongs to, if any
+    pub fn faction(&self) -> Option<&str> {
+        self.faction.as_deref()
+    }
+
+    // Returns a reference for the actor's current fatigue
+    pub fn cur_fatigue(&self) -> &u8 {
+        &self.cur_fatigue
+    }
+
+    // Returns the actor's current exhaustion state, as determined by fatigue
+    pub fn exhaustion(&self) -> Exhaustion {
+        Exhaustion::from(self.cur_fatigue)
+    }
+
+    // Determines if the actor can take on the given amount of fatigue without overflowing
+    pub fn can_exert(&self, amount: usize) -> Result<(), FatigueError> {
+        if (self.cur_fatigue as usize).saturating_add(amount) > u8::MAX as usize {
+            return Err(FatigueError::Overflow(self.cur_fatigue, amount));
+        }
+
+        Ok(())
+    }
+
+    // Returns a reference to the vector of the actor's abilities
+    pub fn abilities(&self) -> &Vec<Ability>{
+        &self.abilities
+    }
+
+    // Returns a reference to the ability with the given UID, if the actor knows it
+    pub fn ability(&self, uid: &[u8; 16]) -> Option<&Ability> {
+        self.abilities.iter().find(|abil| abil.uid() == uid)
+    }
+
+    // Returns a reference to the vector of status effects afflicting the actor
+    pub fn statuses(&self) -> &Vec<StatusEffect> {
+        &self.statuses
+    }
+
+    // Determines if the actor is afflicted by a status of the given kind
+    pub fn has_status(&self, kind: Kind) -> bool {
+        self.statuses.iter().any(|status| status.kind() == kind)
+    }
+
+    // Returns a reference to the actor's combo tracker
+    pub fn combo_tracker(&self) -> &Tracker {
+        &self.combo
+    }
+
+    // Returns a reference to the actor's inventory of essences
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    // Returns the actor's most recent cast, if it may still be in progress
+    pub fn active_cast(&self) -> Option<&ActiveCast> {
+        self.active_cast.as_ref()
+    }
+
+    /// Determines if the actor is able to act, considering both exhaustion and status effects
+    pub fn can_act(&self) -> bool {
+        self.exhaustion().can_act() &&
+        !self.statuses.iter().any(|status| status.kind().is_incapacitating())
+    }
+
+    /// Determines what the actor is doing at the given tick
+    pub fn activity(&self, tick: f64) -> Activity {
+        match self.active_cast {
+            Some(cast)  => cast.activity(tick),
+            None        => Activity::Idle,
+        }
+    }
+
+    /// Determines if the given ability is ready for use at the given tick, or None if the actor
+    /// does not know the ability. An actor that is busy casting or channelling is not ready.
+    pub fn readiness(&self, ability_uid: &[u8; 16], tick: f64) -> Option<Readiness> {
+        let ability = self.ability(ability_uid)?;
+
+        match self.activity(tick) {
+            Activity::Casting(_, until)     |
+            Activity::Channeling(_, until)  => Some(Readiness::Busy(until - tick)),
+            Activity::Idle                  => Some(ability.readiness(tick)),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    // Names the actor
+    pub fn set_name(&mut self, _name: &'static str) {
+        self.name.clear();
+        self.name.push_str(_name);
+    }
+
+    // Sets the actor's maximum health, clamping current health to match
+    pub fn set_max_health(&mut self, max_health: usize) {
+        self.max_health = max_health;
+        self.cur_health = self.cur_health.min(max_health);
+    }
+
+    /// Reduces the actor's health by the given amount, returning the damage actually dealt
+    pub fn damage(&mut self, amount: usize) -> usize {
+        let dealt = amount.min(self.cur_health);
+        self.cur_health -= dealt;
+
+        dealt
+    }
+
+    /// Restores the actor's health by the given amount, returning the health actually restored
+    pub fn heal(&mut self, amount: usize) -> usize {
+        let restored = amount.min(self.max_health - self.cur_health);
+        self.cur_health += restored;
+
+        restored
+    }
+
+    // Sets the actor's moral alignment
+    pub fn set_alignment(&mut self, alignment: Morality) {
+        self.alignment = alignment;
+    }
+
+    // Silences (or un-silences) the actor
+    pub fn set_silenced(&mut self, silenced: bool) {
+        self.silenced = silenced;
+    }
+
+    // Sets the actor's initiative
+    pub fn set_initiative(&mut self, initiative: i32) {
+        self.initiative = initiative;
+    }
+
+    // Sets the faction the actor belongs to, or removes it from its faction if None
+    pub fn set_faction(&mut self, faction: Option<&str>) {
+        self.faction = faction.map(String::from);
+    }
+
+    // Returns a mutable reference to the actor's inventory of essences
+    pub fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    /// Moves actor one cell in the given direction.
+    /// Only the bounds of the grid are checked, so this is reserved for mechanics::movement;
+    /// callers should use movement::step, which respects terrain, obstacles and other actors.
+    pub(crate) fn move_one_cell(&mut self, dir: hex_directions::Side, ctx: &Context) -> Result<(), coords::CoordsError> {
+        let trans = coords::Translation::from(dir);
+        
+        self.pos.translate(&trans, ctx)
+    }
+
+    // Adds ability to actor's ability list
+    pub fn add_ability(&mut self, ability: Ability) {
+        self.abilities.push(ability);
+    }
+
+    /// Begins casting the given ability at the given tick, spending a charge and occupying the
+    /// actor for the ability's cast time and channel duration.
+    /// Returns the readiness at that tick if the ability was not ready.
+    pub fn begin_cast(&mut self, ability_uid: &[u8; 16], tick: f64) -> Result<(), Option<Readiness>> {
+        match self.readiness(ability_uid, tick) {
+            Some(Readiness::Ready)  => {},
+            not_ready               => return Err(not_ready),
+        }
+
+        let ability = self.abilities.iter_mut().find(|abil| abil.uid() == ability_uid).ok_or(None)?;
+        ability.trigger(tick).map_err(Some)?;
+
+        self.active_cast = Some(ActiveCast {
+            ability:    *ability_uid,
+            started:    tick,
+            cast_time:  ability.timing().cast_time,
+            channel:    ability.timing().channel,
+        });
+
+        Ok(())
+    }
+
+    /// Interrupts any cast or channel in progress at the given tick, returning the UID of the
+    /// interrupted ability. A cast interrupted before its cast time elapses never takes effect.
+    /// Charges and cooldowns already spent are not refunded.
+    pub fn interrupt(&mut self, tick: f64) -> Option<[u8; 16]> {
+        match self.activity(tick) {
+            Activity::Casting(uid, _)       |
+            Activity::Channeling(uid, _)    => {
+                self.active_cast = None;
+                Some(uid)
+            },
+            Activity::Idle                  => None,
+        }
+    }
+
+    /// Afflicts the actor with the given status effect at the given tick.
+    ///
+    /// The new status reacts with those already present according to the given interaction matrix:
+    /// statuses it melts, extinguishes, etc. are removed, and its intensity is scaled by the matrix
+    /// multipliers against any that remain. Returns the kinds of status removed by reactions.
+    pub fn apply_status(&mut self, mut status: StatusEffect, matrix: &Matrix, tick: f64) -> Vec<Kind> {
+        let mut removed = Vec::new();
+        let mut multiplier = 1.0;
+
+        for existing in self.statuses.iter().filter(|existing| existing.kind() != status.kind()) {
+            let interaction = matrix.get(status.element(), existing.element());
+            match interaction.reaction {
+                Reaction::Melt          |
+                Reaction::Evaporate     |
+                Reaction::Extinguish    |
+                Reaction::Illuminate    |
+                Reaction::Obscure       => removed.push(existing.kind()),
+                _                       => multiplier *= interaction.multiplier,
+            }
+        }
+        self.statuses.retain(|existing| !removed.contains(&existing.kind()));
+
+        match self.statuses.iter_mut().find(|existing| existing.kind() == status.kind()) {
+            Some(existing)  => existing.reapply(tick),
+            None            => {
+                status.scale(multiplier);
+                self.statuses.push(status);
+            }
+        }
+
+        removed
+    }
+
+    /// Updates all status effects over the given span ending at the given tick, dealing any damage
+    /// over time and removing expired statuses.
+    pub fn update_statuses(&mut self, tick: f64, elapsed: Duration) -> Vec<StatusTick> {
+        let mut ticks = Vec::new();
+
+        for status in self.statuses.iter_mut() {
+            let damage = status.update(tick, elapsed.as_secs_f64());
+            if damage > 0 {
+                ticks.push(StatusTick {
+                    kind:   status.kind(),
+                    source: *status.source(),
+                    damage: self.cur_health.min(damage),
+                });
+                self.cur_health = self.cur_health.saturating_sub(damage);
+            }
+        }
+        self.statuses.retain(|status| !status.is_expired(tick));
+
+        ticks
+    }
+
+    /// Adds the given amount of fatigue to the actor, returning the resulting exhaustion state.
+    /// Fatigue is left untouched if the addition would overflow.
+    pub fn exert(&mut self, amount: usize) -> Result<Exhaustion, FatigueError> {
+        self.can_exert(amount)?;
+
+        self.cur_fatigue += amount as u8;
+        Ok(self.exhaustion())
+    }
+
+    /// Applies the fatigue cost of using the given ability to the actor
+    pub fn use_ability(&mut self, ability_uid: &[u8; 16]) -> Result<Exhaustion, FatigueError> {
+        let cost = match self.ability(ability_uid) {
+            Some(ability)   => fatigue::cost(ability),
+            None            => return Err(FatigueError::UnknownAbility(*ability_uid)),
+        };
+
+        self.exert(cost)
+    }
+
+    /// Records a use of an ability for combo recognition
+    pub fn record_usage(&mut self, usage: Usage, completed_combo: bool) {
+        self.combo.record(usage, completed_combo);
+    }
+
+    /// Ends the actor's turn, for the purposes of turn-windowed combos
+    pub fn end_turn(&mut self) {
+        self.combo.end_turn();
+    }
+
+    /// Recovers fatigue over the given span of game time, returning the resulting exhaustion state
+    pub fn recover_fatigue(&mut self, elapsed: Duration) -> Exhaustion {
+        let (recovered, carry) = fatigue::recovery(elapsed.as_secs_f64(), self.fatigue_carry);
+
+        self.cur_fatigue = self.cur_fatigue.saturating_sub(recovered);
+        self.fatigue_carry = if self.cur_fatigue == 0 { 0.0 } else { carry };
+
+        self.exhaustion()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+// Display output format for actors
+// [UID]:[Name]:[Position]:[Fatigue]:[Abilities (CSV)]
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut res = write!(f, "{}:{}:{}:{}:|", Uuid::from_bytes(self.uid), self.name, self.pos, self.cur_fatigue);
+
+        for (i, abil) in self.abilities.iter().enumerate() {
+            res = write!(f, "{}", abil);
+
+            // Avoid adding a trailing semicolon
+            if i == self.abilities.len()-1 {
+                res = write!(f, ";");
+            }
+        }
+
+        res
+    }
+}
+impl Plottable for Actor {
+    fn origin(&self) -> &coords::Position {
+        &self.pos
+    }
+}
+impl Randomizable for Actor {
+    fn rand(ctx: &Context) -> Self {
+        // Generate UUID
+        let uid = *Uuid::new_v4().as_bytes();
+
+        // Cache the RNG thread
+        let mut rng = rand::thread_rng();
+
+        // Generate random name
+        let name: String = rng.sample_iter(&Alphanumeric).take(10).collect();
+
+        // Generate a random position
+        let pos: coords::Position = coords::Position::rand(ctx);
+
+        // Generate a random alignment
+        let alignment = Morality::rand(ctx);
+
+        // New actor, so fatigue should be 0
+        let cur_fatigue = 0;
+
+        // Generate random abilities
+        let mut abilities: Vec<Ability> = Vec::new();
+        let num_abilities: u32 = rng.gen_range(0, 10);
+        for _i in 0 .. num_abilities {
+            abilities.push(Ability::rand(ctx));
+        }
+
+        Self {
+            uid,
+            name,
+            pos,
+            cur_health: DEFAULT_MAX_HEALTH,
+            max_health: DEFAULT_MAX_HEALTH,
+            alignment,
+            silenced:   false,
+            initiative: 0,
+            faction:    None,
+            cur_fatigue,
+            fatigue_carry: 0.0,
+            abilities,
+            active_cast: None,
+            statuses:   Vec::new(),
+            combo:      Tracker::new(),
+            inventory:  Inventory::default(),
+        }
+    }
+}
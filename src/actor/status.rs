@@ -0,0 +1,293 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : actor/status.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines status effects: lasting conditions left on an actor
+    by an ability.
+
+    Each status is tied to the element of its source, and follows a
+    polynomial intensity curve over its duration, just as weather does.
+    Damaging statuses deal their intensity (per stack) as damage per second of
+    game time, integrated over each update so that statuses expiring part-way
+    through an update still deal their damage.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use crate::{
+    element::Element,
+    polyfunc::PolyFunc,
+};
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of all status effects
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Hash,
+    Serialize, Deserialize
+)]
+pub enum Kind {
+    Burning,
+    Frozen,
+    Soaked,
+    Shocked,
+    Blinded,
+    Hastened,
+}
+
+/// Enumeration of the ways a status may respond to being re-applied
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Stacking {
+    Refresh,        // Duration is restarted
+    Stack(u8),      // A stack is added (up to the given maximum) and duration is restarted
+    Ignore,         // Re-application has no effect
+}
+
+/// A status effect attached to an actor
+#[derive(
+    Debug,
+    Clone,
+    Serialize, Deserialize
+)]
+pub struct StatusEffect {
+    kind:           Kind,
+    element:        Element,
+    source:         [u8; 16],
+    stacks:         u8,
+    intensity:      PolyFunc,
+    damage_carry:   f64,
+}
+
+/// Specification of a status an ability inflicts upon the actors it affects
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Affliction {
+    pub kind:       Kind,
+    pub duration:   f64,
+    pub magnitude:  f64,
+}
+
+/// Report of the damage dealt by a status during an update
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StatusTick {
+    pub kind:   Kind,
+    pub source: [u8; 16],
+    pub damage: usize,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Kind {
+    /// Returns the element a status of this kind is most naturally associated with
+    pub fn natural_element(&self) -> Element {
+        match self {
+            Kind::Burning   => Element::Fire,
+            Kind::Frozen    => Element::Ice,
+            Kind::Soaked    => Element::Water,
+            Kind::Shocked   => Element::Electric,
+            Kind::Blinded   => Element::Light,
+            Kind::Hastened  => Element::Wind,
+        }
+    }
+
+    /// Returns the stacking rule for statuses of this kind
+    pub fn stacking(&self) -> Stacking {
+        match self {
+            Kind::Burning   => Stacking::Stack(3),
+            Kind::Frozen    => Stacking::Refresh,
+            Kind::Soaked    => Stacking::Refresh,
+            Kind::Shocked   => Stacking::Stack(2),
+            Kind::Blinded   => Stacking::Refresh,
+            Kind::Hastened  => Stacking::Ignore,
+        }
+    }
+
+    /// Determines if statuses of this kind deal damage over time
+    pub fn is_damaging(&self) -> bool {
+        matches!(self, Kind::Burning | Kind::Shocked)
+    }
+
+    /// Determines if statuses of this kind prevent the afflicted actor from acting
+    pub fn is_incapacitating(&self) -> bool {
+        *self == Kind::Frozen
+    }
+}
+
+impl Affliction {
+    /// Fully-qualified constructor
+    pub fn new(kind: Kind, duration: f64, magnitude: f64) -> Self {
+        Self {kind, duration, magnitude}
+    }
+}
+
+impl StatusEffect {
+    /// Fully-qualified constructor.
+    /// Intensity peaks at the given magnitude halfway through the given duration.
+    pub fn new(kind:        Kind,
+               element:     Element,
+               source:      [u8; 16],
+               applied_at:  f64,
+               duration:    f64,
+               magnitude:   f64) -> Self {
+        Self {
+            kind,
+            element,
+            source,
+            stacks:         1,
+            intensity:      PolyFunc::new(magnitude, duration, applied_at),
+            damage_carry:   0.0,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn element(&self) -> Element {
+        self.element
+    }
+
+    pub fn source(&self) -> &[u8; 16] {
+        &self.source
+    }
+
+    pub fn stacks(&self) -> u8 {
+        self.stacks
+    }
+
+    pub fn magnitude(&self) -> f64 {
+        self.intensity.magnitude()
+    }
+
+    /// Returns the tick at which the status expires
+    pub fn expires_at(&self) -> f64 {
+        self.intensity.start_time() + self.intensity.duration()
+    }
+
+    /// Returns the intensity of the status at the given tick, accounting for stacks
+    pub fn intensity(&self, tick: f64) -> f64 {
+        if self.is_expired(tick) {
+            return 0.0;
+        }
+
+        self.intensity.solve(tick).max(0.0) * self.stacks as f64
+    }
+
+    pub fn is_expired(&self, tick: f64) -> bool {
+        tick >= self.expires_at()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Re-applies the status at the given tick according to its stacking rule
+    pub fn reapply(&mut self, tick: f64) {
+        match self.kind.stacking() {
+            Stacking::Refresh       => {},
+            Stacking::Stack(max)    => self.stacks = u8::min(self.stacks + 1, max),
+            Stacking::Ignore        => return,
+        }
+
+        self.intensity.set_start_time(tick);
+    }
+
+    /// Scales the peak intensity of the status by the given factor
+    pub fn scale(&mut self, factor: f64) {
+        let magnitude = self.intensity.magnitude() * factor;
+        self.intensity.set_magnitude(magnitude);
+    }
+
+    /// Determines the damage dealt by the status over the given span ending at the given tick,
+    /// up to its expiry. Fractional damage is carried into the next update.
+    pub fn update(&mut self, tick: f64, elapsed_secs: f64) -> usize {
+        if !self.kind.is_damaging() {
+            return 0;
+        }
+
+        let from = tick - elapsed_secs.max(0.0);
+        let dealt = self.intensity.integrate(from, tick).max(0.0) * self.stacks as f64;
+        let total = dealt + self.damage_carry;
+        self.damage_carry = total.fract();
+
+        total.trunc() as usize
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacking_and_expiry() {
+        let mut burning = StatusEffect::new(Kind::Burning, Element::Fire, [0; 16], 0.0, 4.0, 10.0);
+
+        // Stacks are capped at the kind's maximum
+        for _ in 0..5 {
+            burning.reapply(0.0);
+        }
+        assert_eq!(burning.stacks(), 3);
+        assert!(burning.intensity(2.0) > 0.0);
+
+        assert!(burning.is_expired(4.0));
+        assert_eq!(burning.intensity(4.0), 0.0);
+        assert_eq!(burning.update(5.0, 1.0), 0);
+    }
+
+    #[test]
+    fn damage_through_expiry() {
+        // Peak of 10 at 2s: the whole curve deals 2/3 * 10 * 4 = 26.67 damage
+        let mut burning = StatusEffect::new(Kind::Burning, Element::Fire, [0; 16], 0.0, 4.0, 10.0);
+
+        // A single update spanning the whole status, and beyond, still deals all of its damage
+        assert_eq!(burning.update(6.0, 6.0), 26);
+        assert!(burning.is_expired(6.0));
+        assert_eq!(burning.update(12.0, 6.0), 0);
+    }
+
+    #[test]
+    fn non_damaging() {
+        let mut frozen = StatusEffect::new(Kind::Frozen, Element::Ice, [0; 16], 0.0, 4.0, 10.0);
+
+        assert_eq!(frozen.update(2.0, 1.0), 0);
+        assert!(Kind::Frozen.is_incapacitating());
+    }
+}
@@ -0,0 +1,774 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ai/behaviour.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines a behaviour tree runtime for actor decision making.
+
+    Trees are composed of sequences, selectors, parallels, decorators,
+    conditions and actions, and are plain data which may be loaded from JSON.
+    Trees are reactive: every tick evaluates the tree from its root, so any
+    progress that must persist between ticks (e.g. a path being followed) is
+    kept in the actor's blackboard rather than in the nodes themselves.
+
+    A single tree may drive any number of actors, each of which is given its
+    own blackboard.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::collections::HashMap;
+
+use crate::{
+    ability::cast::{
+        self,
+        Target,
+    },
+    actor::{
+        faction,
+        inventory,
+    },
+    coords,
+    element::Elemental,
+    hex_directions,
+    mechanics::{
+        movement,
+        resource::State,
+        weather::Intensity,
+    },
+    world::World,
+    Plottable,
+};
+
+use super::utility;
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Maximum movement cost an actor will spend reaching shelter from the weather
+const MAX_SHELTER_COST: u32 = 12;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the results of ticking a node
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+/// Enumeration of the values that may be held in a blackboard
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Value {
+    Flag(bool),
+    Number(f64),
+    Text(String),
+    Uid([u8; 16]),
+    Position(coords::Position),
+    Path(Vec<coords::Position>),
+}
+
+/// Per-actor memory shared between the nodes of a tree
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Blackboard {
+    entries: HashMap<String, Value>,
+}
+
+/// Enumeration of the policies by which a parallel node combines its children
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Policy {
+    RequireAll,     // Succeeds once all children succeed, and fails as soon as any fails
+    RequireOne,     // Succeeds as soon as any child succeeds, and fails once all fail
+}
+
+/// Enumeration of the ways a decorator may alter the result of its child
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Decorator {
+    Invert,         // Swaps success and failure
+    Succeed,        // Reports success unless running
+    Fail,           // Reports failure unless running
+    Repeat(u32),    // Ticks the child up to the given number of times while it succeeds
+}
+
+/// Enumeration of the conditions a tree may check
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Condition {
+    IsSet(String),          // The blackboard holds the given key
+    Flag(String),           // The blackboard holds a true flag under the given key
+    FatigueAtLeast(u8),
+    HealthBelow(f64),       // Fraction of maximum health
+    HostileWithin(usize),
+    OnResource,             // The actor stands within a resource that is not depleted
+    WeatherAbove(f64),      // Exact intensity of the current weather
+}
+
+/// Enumeration of the built-in actions a tree may take
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Leaf {
+    Set(String, Value),
+    Clear(String),
+    FindHostile(String),                        // Records the nearest hostile actor under the given key
+    PlanPath {target: String, path: String},    // Records the cheapest path to the actor or position under the target key
+    MoveAlongPath(String),                      // Takes a single step along the path under the given key
+    CastAbility {ability: String, target: String}, // Casts the named ability at the actor or position under the target key
+    ConsumeResource(String),                    // Harvests the resource the actor stands in, recording the essence gained
+    FleeWeather,                                // Takes a single step towards shelter while the weather is strong
+}
+
+/// A node of a behaviour tree
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Node {
+    Sequence(Vec<Node>),
+    Selector(Vec<Node>),
+    Parallel(Policy, Vec<Node>),
+    Decorator(Decorator, Box<Node>),
+    Condition(Condition),
+    Action(Leaf),
+}
+
+/// A behaviour tree, along with the blackboards of the actors it drives
+#[derive(Debug, Clone, PartialEq)]
+pub struct Brain {
+    tree:           Node,
+    blackboards:    HashMap<[u8; 16], Blackboard>,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Blackboard {
+    /// Constructs an empty blackboard
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.get(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn set(&mut self, key: &str, value: Value) {
+        self.entries.insert(key.to_string(), value);
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.entries.remove(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Node {
+    /// Constructs a tree from the given JSON
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Evaluates the node on behalf of the given actor
+    pub fn tick(&self, world: &mut World, actor_uid: &[u8; 16], blackboard: &mut Blackboard) -> Status {
+        match self {
+            Node::Sequence(children)            => {
+                for child in children.iter() {
+                    match child.tick(world, actor_uid, blackboard) {
+                        Status::Success => continue,
+                        status          => return status,
+                    }
+                }
+                Status::Success
+            },
+            Node::Selector(children)            => {
+                for child in children.iter() {
+                    match child.tick(world, actor_uid, blackboard) {
+                        Status::Failure => continue,
+                        status          => return status,
+                    }
+                }
+                Status::Failure
+            },
+            Node::Parallel(policy, children)    => {
+                let statuses: Vec<Status> = children.iter()
+                    .map(|child| child.tick(world, actor_uid, blackboard))
+                    .collect();
+                let (decisive, other) = match policy {
+                    Policy::RequireAll  => (Status::Failure, Status::Success),
+                    Policy::RequireOne  => (Status::Success, Status::Failure),
+                };
+
+                if statuses.contains(&decisive) {
+                    decisive
+                } else if statuses.iter().all(|status| *status == other) {
+                    other
+                } else {
+                    Status::Running
+                }
+            },
+            Node::Decorator(decorator, child)   => decorate(*decorator, child, world, actor_uid, blackboard),
+            Node::Condition(condition)          => {
+                if condition.holds(world, actor_uid, blackboard) {
+                    Status::Success
+                } else {
+                    Status::Failure
+                }
+            },
+            Node::Action(leaf)                  => leaf.perform(world, actor_uid, blackboard),
+        }
+    }
+}
+
+impl Condition {
+    /// Determines if the condition holds for the given actor
+    pub fn holds(&self, world: &World, actor_uid: &[u8; 16], blackboard: &Blackboard) -> bool {
+        let actor = match world.actor(actor_uid) {
+            Some(actor) => actor,
+            None        => return false,
+        };
+
+        match self {
+            Condition::IsSet(key)               => blackboard.contains(key),
+            Condition::Flag(key)                => blackboard.get(key) == Some(&Value::Flag(true)),
+            Condition::FatigueAtLeast(fatigue)  => actor.cur_fatigue() >= fatigue,
+            Condition::HealthBelow(fraction)    => {
+                (actor.cur_health() as f64) < fraction * actor.max_health() as f64
+            },
+            Condition::HostileWithin(distance)  => !faction::hostiles_within(world, actor_uid, *distance).is_empty(),
+            Condition::OnResource               => {
+                world.resources().iter().any(|resource| resource.state() != State::Depleted && resource.covers(actor.origin()))
+            },
+            Condition::WeatherAbove(intensity)  => world.weather().intensity_exact(world.tick()) > *intensity,
+        }
+    }
+}
+
+impl Leaf {
+    /// Performs the action on behalf of the given actor
+    pub fn perform(&self, world: &mut World, actor_uid: &[u8; 16], blackboard: &mut Blackboard) -> Status {
+        let origin = match world.actor(actor_uid) {
+            Some(actor) => *actor.origin(),
+            None        => return Status::Failure,
+        };
+
+        match self {
+            Leaf::Set(key, value)               => {
+                blackboard.set(key, value.clone());
+                Status::Success
+            },
+            Leaf::Clear(key)                    => {
+                blackboard.remove(key);
+                Status::Success
+            },
+            Leaf::FindHostile(key)              => {
+                let hostile = world.actor(actor_uid)
+                    .and_then(|actor| utility::nearest_hostile(world, actor))
+                    .map(|hostile| *hostile.uid());
+                match hostile {
+                    Some(uid)   => {
+                        blackboard.set(key, Value::Uid(uid));
+                        Status::Success
+                    },
+                    None        => {
+                        blackboard.remove(key);
+                        Status::Failure
+                    },
+                }
+            },
+            Leaf::PlanPath {target, path}       => {
+                let (goal, occupied) = match blackboard.get(target) {
+                    Some(Value::Uid(uid))       => match world.actor(uid) {
+                        Some(actor) => (*actor.origin(), true),
+                        None        => return Status::Failure,
+                    },
+                    Some(Value::Position(pos))  => (*pos, false),
+                    _                           => return Status::Failure,
+                };
+
+                // Stop beside any actor being approached, at the cheapest cell from which to reach it
+                let reached = movement::search(world, actor_uid, u32::MAX);
+                let dest = if occupied {
+                    reached.iter()
+                        .filter(|(pos, _)| pos.is_neighbor(&goal))
+                        .min_by_key(|&(pos, &(cost, _))| (cost, origin.distance_to(pos), pos.x(), pos.y(), pos.z()))
+                        .map(|(pos, _)| *pos)
+                } else {
+                    Some(goal)
+                };
+
+                match dest.and_then(|dest| movement::route(&reached, &dest)) {
+                    Some(waypoints) => {
+                        blackboard.set(path, Value::Path(waypoints));
+                        Status::Success
+                    },
+                    None            => Status::Failure,
+                }
+            },
+            Leaf::MoveAlongPath(key)            => {
+                let mut waypoints = match blackboard.get(key) {
+                    Some(Value::Path(waypoints))    => waypoints.clone(),
+                    _                               => return Status::Failure,
+                };
+                if waypoints.is_empty() {
+                    return Status::Success;
+                }
+
+                let next = waypoints.remove(0);
+                if !origin.is_neighbor(&next) {
+                    return Status::Failure;
+                }
+                let dir = hex_directions::Side::from(origin.delta_to(&next));
+                if movement::step(world, actor_uid, dir, u32::MAX).is_err() {
+                    return Status::Failure;
+                }
+
+                let status = if waypoints.is_empty() { Status::Success } else { Status::Running };
+                blackboard.set(key, Value::Path(waypoints));
+                status
+            },
+            Leaf::CastAbility {ability, target} => {
+                let ability_uid = world.actor(actor_uid)
+                    .and_then(|actor| actor.abilities().iter().find(|known| known.name() == ability))
+                    .map(|known| *known.uid());
+                let target = match blackboard.get(target) {
+                    Some(Value::Uid(uid))       => Target::Actor(*uid),
+                    Some(Value::Position(pos))  => Target::Cell(*pos),
+                    _                           => return Status::Failure,
+                };
+
+                match ability_uid.map(|uid| cast::execute(world, actor_uid, &uid, &target)) {
+                    Some(Ok(_)) => Status::Success,
+                    _           => Status::Failure,
+                }
+            },
+            Leaf::ConsumeResource(key)          => {
+                let resource = world.resources().iter()
+                    .find(|resource| resource.state() != State::Depleted && resource.covers(&origin))
+                    .map(|resource| *resource.uid());
+
+                match resource.map(|uid| inventory::harvest(world, actor_uid, &uid)) {
+                    Some(Ok(stored))    => {
+                        blackboard.set(key, Value::Number(stored as f64));
+                        Status::Success
+                    },
+                    _                   => Status::Failure,
+                }
+            },
+            Leaf::FleeWeather                   => flee_weather(world, actor_uid, &origin),
+        }
+    }
+}
+
+impl Brain {
+    /// Constructs a brain driven by the given tree
+    pub fn new(tree: Node) -> Self {
+        Self {
+            tree,
+            blackboards: HashMap::new(),
+        }
+    }
+
+    /// Constructs a brain driven by the tree described by the given JSON
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        Ok(Self::new(Node::from_json(json)?))
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn tree(&self) -> &Node {
+        &self.tree
+    }
+
+    pub fn blackboard(&self, actor_uid: &[u8; 16]) -> Option<&Blackboard> {
+        self.blackboards.get(actor_uid)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the given actor's blackboard, creating an empty one if needed
+    pub fn blackboard_mut(&mut self, actor_uid: &[u8; 16]) -> &mut Blackboard {
+        self.blackboards.entry(*actor_uid).or_default()
+    }
+
+    /// Forgets the given actor's blackboard
+    pub fn forget(&mut self, actor_uid: &[u8; 16]) {
+        self.blackboards.remove(actor_uid);
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Evaluates the tree on behalf of the given actor
+    pub fn tick(&mut self, world: &mut World, actor_uid: &[u8; 16]) -> Status {
+        let blackboard = self.blackboards.entry(*actor_uid).or_default();
+
+        self.tree.tick(world, actor_uid, blackboard)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Applies the given decorator to the result of ticking the given child
+fn decorate(decorator:  Decorator,
+            child:      &Node,
+            world:      &mut World,
+            actor_uid:  &[u8; 16],
+            blackboard: &mut Blackboard) -> Status {
+    match decorator {
+        Decorator::Invert       => match child.tick(world, actor_uid, blackboard) {
+            Status::Success => Status::Failure,
+            Status::Failure => Status::Success,
+            Status::Running => Status::Running,
+        },
+        Decorator::Succeed      => match child.tick(world, actor_uid, blackboard) {
+            Status::Running => Status::Running,
+            _               => Status::Success,
+        },
+        Decorator::Fail         => match child.tick(world, actor_uid, blackboard) {
+            Status::Running => Status::Running,
+            _               => Status::Failure,
+        },
+        Decorator::Repeat(times) => {
+            for _ in 0..times {
+                match child.tick(world, actor_uid, blackboard) {
+                    Status::Success => continue,
+                    status          => return status,
+                }
+            }
+            Status::Success
+        },
+    }
+}
+
+/// Takes a single step towards the nearest sheltered cell while the weather is strong.
+/// Cells adjacent to solid obstacles are considered sheltered.
+fn flee_weather(world: &mut World, actor_uid: &[u8; 16], origin: &coords::Position) -> Status {
+    if let Intensity::None | Intensity::Mild = world.weather().intensity(world.tick()) {
+        return Status::Success;
+    }
+    if is_sheltered(world, origin) {
+        return Status::Success;
+    }
+
+    // Ties are broken by position so that the choice never rests on iteration order
+    let reached = movement::search(world, actor_uid, MAX_SHELTER_COST);
+    let shelter = reached.iter()
+        .filter(|(pos, _)| is_sheltered(world, pos))
+        .min_by_key(|&(pos, &(cost, _))| (cost, origin.distance_to(pos), pos.x(), pos.y(), pos.z()))
+        .map(|(pos, _)| *pos);
+
+    // Follow the route found by the search rather than stepping greedily towards the shelter
+    let next = match shelter.and_then(|shelter| movement::route(&reached, &shelter)) {
+        Some(route) if !route.is_empty()    => route[0],
+        _                                   => return Status::Failure,
+    };
+
+    let dir = hex_directions::Side::from(origin.delta_to(&next));
+    match movement::step(world, actor_uid, dir, u32::MAX) {
+        Ok(_)   => Status::Running,
+        Err(_)  => Status::Failure,
+    }
+}
+
+/// Determines if the given cell lies in the lee of a solid obstacle
+fn is_sheltered(world: &World, pos: &coords::Position) -> bool {
+    pos.ring(1, world.ctx()).iter().any(|neighbour| {
+        world.obstacles().iter()
+            .any(|obstacle| obstacle.contains(neighbour) && movement::is_solid(obstacle.element()))
+    })
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ability::{
+            Ability,
+            aspect::*,
+        },
+        actor::{
+            Actor,
+            faction::{
+                Relations,
+                Relationship,
+            },
+        },
+        context::Context,
+        coords::tests::pos,
+        element::Element,
+        mechanics::{
+            obstacle::Obstacle,
+            resource::Resource,
+            weather,
+        },
+        polyfunc::PolyFunc,
+    };
+
+    #[test]
+    fn composites() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let actor = Actor::new("Sentry", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let uid = *actor.uid();
+        world.add_actor(actor);
+
+        let mut brain = Brain::from_json(r#"{"Selector": [
+            {"Sequence": [
+                {"Condition": {"Flag": "alert"}},
+                {"Action": {"Set": ["mode", {"Text": "fight"}]}}
+            ]},
+            {"Decorator": ["Invert", {"Condition": {"IsSet": "mode"}}]},
+            {"Action": {"Set": ["mode", {"Text": "idle"}]}}
+        ]}"#).unwrap();
+
+        // Not alert, but no mode yet: the inverted check succeeds first
+        assert_eq!(brain.tick(&mut world, &uid), Status::Success);
+        assert!(brain.blackboard(&uid).unwrap().get("mode").is_none());
+
+        brain.blackboard_mut(&uid).set("alert", Value::Flag(true));
+        assert_eq!(brain.tick(&mut world, &uid), Status::Success);
+        assert_eq!(brain.blackboard(&uid).unwrap().get("mode"), Some(&Value::Text("fight".to_string())));
+
+        let parallel = Node::Parallel(Policy::RequireAll, vec![
+            Node::Condition(Condition::IsSet("mode".to_string())),
+            Node::Condition(Condition::HostileWithin(5)),
+        ]);
+        assert_eq!(parallel.tick(&mut world, &uid, brain.blackboard_mut(&uid)), Status::Failure);
+    }
+
+    #[test]
+    fn hunt() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let mut relations = Relations::new();
+        relations.set("Crown", "Bandits", Relationship::Hostile);
+        world.set_relations(relations);
+
+        let strike = Ability::new("Strike", 10, Aspects::new(Aesthetics::Unset, Element::Unset, Method::Manual, Morality::Neutral, School::Destruction))
+            .with_range(1);
+        let mut knight = Actor::new("Knight", pos(0, 0, 0, &ctx), 0, vec![strike]);
+        knight.set_faction(Some("Crown"));
+        let knight_uid = *knight.uid();
+        let mut bandit = Actor::new("Bandit", pos(4, 0, -4, &ctx), 0, Vec::new());
+        bandit.set_faction(Some("Bandits"));
+        let bandit_uid = *bandit.uid();
+        world.add_actor(knight);
+        world.add_actor(bandit);
+
+        let mut brain = Brain::from_json(r#"{"Sequence": [
+            {"Action": {"FindHostile": "enemy"}},
+            {"Selector": [
+                {"Sequence": [
+                    {"Condition": {"HostileWithin": 1}},
+                    {"Action": {"CastAbility": {"ability": "Strike", "target": "enemy"}}}
+                ]},
+                {"Sequence": [
+                    {"Action": {"PlanPath": {"target": "enemy", "path": "route"}}},
+                    {"Action": {"MoveAlongPath": "route"}}
+                ]}
+            ]}
+        ]}"#).unwrap();
+
+        // Close the distance one step at a time, then strike
+        assert_eq!(brain.tick(&mut world, &knight_uid), Status::Running);
+        assert_eq!(brain.tick(&mut world, &knight_uid), Status::Running);
+        assert_eq!(brain.tick(&mut world, &knight_uid), Status::Success);
+        assert_eq!(world.actor(&knight_uid).unwrap().origin(), &pos(3, 0, -3, &ctx));
+        assert_eq!(brain.tick(&mut world, &knight_uid), Status::Success);
+        assert!(world.actor(&bandit_uid).unwrap().cur_health() < world.actor(&bandit_uid).unwrap().max_health());
+    }
+
+    #[test]
+    fn plan_around_obstacles() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let knight = Actor::new("Knight", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let knight_uid = *knight.uid();
+        let bandit = Actor::new("Bandit", pos(4, 0, -4, &ctx), 0, Vec::new());
+        let bandit_uid = *bandit.uid();
+        world.add_actor(knight);
+        world.add_actor(bandit);
+
+        // A wall across the straight line between the two
+        let wall = Obstacle::new(vec![pos(2, -1, -1, &ctx), pos(2, 0, -2, &ctx), pos(2, 1, -3, &ctx)], Element::Earth).unwrap();
+        world.add_obstacle(wall);
+
+        let mut blackboard = Blackboard::new();
+        blackboard.set("enemy", Value::Uid(bandit_uid));
+        let plan = Node::Action(Leaf::PlanPath {target: "enemy".to_string(), path: "route".to_string()});
+        assert_eq!(plan.tick(&mut world, &knight_uid, &mut blackboard), Status::Success);
+        match blackboard.get("route") {
+            Some(Value::Path(waypoints))    => {
+                assert!(waypoints.iter().all(|waypoint| world.obstacles().iter().all(|obstacle| !obstacle.contains(waypoint))));
+                assert!(waypoints.last().unwrap().is_neighbor(&pos(4, 0, -4, &ctx)));
+            },
+            other                           => panic!("expected a path, found {:?}", other),
+        }
+
+        let walk = Node::Action(Leaf::MoveAlongPath("route".to_string()));
+        let mut status = Status::Running;
+        for _ in 0..10 {
+            status = walk.tick(&mut world, &knight_uid, &mut blackboard);
+            if status != Status::Running {
+                break;
+            }
+        }
+        assert_eq!(status, Status::Success);
+        assert!(world.actor(&knight_uid).unwrap().origin().is_neighbor(&pos(4, 0, -4, &ctx)));
+
+        // Positions which cannot be reached at all yield no path
+        blackboard.set("spot", Value::Position(pos(2, 0, -2, &ctx)));
+        let plan = Node::Action(Leaf::PlanPath {target: "spot".to_string(), path: "route".to_string()});
+        assert_eq!(plan.tick(&mut world, &knight_uid, &mut blackboard), Status::Failure);
+    }
+
+    #[test]
+    fn flee_around_actors() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let druid = Actor::new("Druid", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let uid = *druid.uid();
+        world.add_actor(druid);
+        world.set_weather(weather::Event::new(Element::Water, PolyFunc::new(255.0, 100.0, 0.0)));
+        world.advance(std::time::Duration::from_secs(50));
+
+        // The only step towards the rock is blocked, so the route must first turn aside
+        world.add_actor(Actor::new("Bystander", pos(1, 0, -1, &ctx), 0, Vec::new()));
+        let rock = Obstacle::new(vec![pos(4, 0, -4, &ctx)], Element::Earth).unwrap();
+        world.add_obstacle(rock);
+
+        let mut blackboard = Blackboard::new();
+        let flee = Node::Action(Leaf::FleeWeather);
+        let mut status = Status::Running;
+        for _ in 0..10 {
+            status = flee.tick(&mut world, &uid, &mut blackboard);
+            if status != Status::Running {
+                break;
+            }
+        }
+        assert_eq!(status, Status::Success);
+        assert_eq!(world.actor(&uid).unwrap().origin(), &pos(3, 0, -3, &ctx));
+    }
+
+    #[test]
+    fn resources_and_weather() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let actor = Actor::new("Druid", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let uid = *actor.uid();
+        world.add_actor(actor);
+        world.add_resource(Resource::new(Element::Water, State::High, pos(0, 0, 0, &ctx), 1));
+
+        let mut blackboard = Blackboard::new();
+        let consume = Node::Action(Leaf::ConsumeResource("drawn".to_string()));
+        assert_eq!(consume.tick(&mut world, &uid, &mut blackboard), Status::Success);
+        assert_eq!(blackboard.get("drawn"), Some(&Value::Number(3.0)));
+        assert_eq!(world.resources()[0].state(), State::Partial);
+        assert_eq!(world.actor(&uid).unwrap().inventory().amount(Element::Water), 3);
+
+        // Calm weather needs no fleeing
+        let flee = Node::Action(Leaf::FleeWeather);
+        assert_eq!(flee.tick(&mut world, &uid, &mut blackboard), Status::Success);
+
+        // In a storm, make for the lee of the nearest rock
+        world.set_weather(weather::Event::new(Element::Water, PolyFunc::new(255.0, 100.0, 0.0)));
+        world.advance(std::time::Duration::from_secs(50));
+        let rock = Obstacle::new(vec![pos(3, 0, -3, &ctx)], Element::Earth).unwrap();
+        world.add_obstacle(rock);
+
+        assert_eq!(flee.tick(&mut world, &uid, &mut blackboard), Status::Running);
+        assert_eq!(world.actor(&uid).unwrap().origin(), &pos(1, 0, -1, &ctx));
+        assert_eq!(flee.tick(&mut world, &uid, &mut blackboard), Status::Running);
+        assert_eq!(flee.tick(&mut world, &uid, &mut blackboard), Status::Success);
+    }
+}
@@ -0,0 +1,568 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ai/influence.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines influence maps over the hex grid.
+
+    Each layer gathers influence from the kinds of entity it listens to:
+    actors, resources, or the weather. Actors and resources project their
+    strength outwards from their origin, decaying with distance out to the
+    layer's radius. The weather, which blankets the whole grid, contributes an
+    ambient level of influence to every cell.
+
+    Layers remember the projection of every source, so that refreshing a
+    layer against the world only reprojects the sources that have moved or
+    changed strength since the last refresh.
+
+    Layers are combined by goals, which weigh each layer by name. A goal may
+    then be used to find the best cell within reach of a position.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::collections::HashMap;
+
+use crate::{
+    actor::faction::{
+        self,
+        Relationship,
+    },
+    context::Context,
+    coords,
+    element::{
+        Element,
+        Elemental,
+    },
+    mechanics::{
+        movement,
+        resource::State,
+    },
+    world::World,
+    Plottable,
+};
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Influence below which a cell is considered uninfluenced
+const INFLUENCE_EPSILON: f64 = 1e-9;
+
+/// Exact weather intensity corresponding to full ambient influence
+const MAX_WEATHER_INTENSITY: f64 = 255.0;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the entities which may project influence
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Hash,
+    Serialize, Deserialize
+)]
+pub enum Source {
+    Actor([u8; 16]),
+    Resource([u8; 16]),
+}
+
+/// Enumeration of the kinds of entity a layer gathers influence from
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Emitter {
+    Hostiles([u8; 16]),         // Actors hostile to the given actor, by fraction of health remaining
+    Allies([u8; 16]),           // Actors allied with the given actor (save itself), by fraction of health remaining
+    Resources(Option<Element>), // Resources of the given element (or any), by fullness
+    Weather(Option<Element>),   // Weather of the given element (or any), by intensity
+}
+
+/// Enumeration of the ways influence decays with distance from its source
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Decay {
+    Linear,             // Falls away evenly, reaching zero just beyond the layer's radius
+    Exponential(f64),   // Multiplied by the given factor for every cell of distance
+}
+
+/// The projection of a single source onto a layer
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Projection {
+    origin:     coords::Position,
+    strength:   f64,
+}
+
+/// A single layer of influence over the grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    emitters:       Vec<Emitter>,
+    decay:          Decay,
+    radius:         usize,
+    projections:    HashMap<Source, Projection>,
+    values:         HashMap<coords::Position, f64>,
+    ambient:        f64,                            // Influence present in every cell
+}
+
+/// A collection of named layers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InfluenceMap {
+    layers: HashMap<String, Layer>,
+}
+
+/// A weighting of named layers
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Goal {
+    pub weights: Vec<(String, f64)>,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Decay {
+    /// Determines the fraction of a source's strength felt at the given distance
+    pub fn factor(&self, distance: u32, radius: usize) -> f64 {
+        if distance as usize > radius {
+            return 0.0;
+        }
+
+        match *self {
+            Decay::Linear               => 1.0 - distance as f64 / (radius + 1) as f64,
+            Decay::Exponential(factor)  => factor.powi(distance as i32),
+        }
+    }
+}
+
+impl Layer {
+    /// Constructs an empty layer gathering influence from the given emitters
+    pub fn new(emitters: Vec<Emitter>, decay: Decay, radius: usize) -> Self {
+        Self {
+            emitters,
+            decay,
+            radius,
+            projections:    HashMap::new(),
+            values:         HashMap::new(),
+            ambient:        0.0,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn emitters(&self) -> &Vec<Emitter> {
+        &self.emitters
+    }
+
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    pub fn ambient(&self) -> f64 {
+        self.ambient
+    }
+
+    /// Returns the influence felt at the given position
+    pub fn value(&self, pos: &coords::Position) -> f64 {
+        self.ambient + self.values.get(pos).cloned().unwrap_or(0.0)
+    }
+
+    /// Returns the sources currently projecting onto the layer
+    pub fn sources(&self) -> Vec<Source> {
+        self.projections.keys().cloned().collect()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Projects the given source from the given origin, replacing any previous projection of it
+    pub fn project(&mut self, source: Source, origin: coords::Position, strength: f64, ctx: &Context) {
+        self.withdraw(&source, ctx);
+        self.spread(&origin, strength, ctx);
+        self.projections.insert(source, Projection {origin, strength});
+    }
+
+    /// Withdraws the projection of the given source, if any
+    pub fn withdraw(&mut self, source: &Source, ctx: &Context) {
+        if let Some(projection) = self.projections.remove(source) {
+            self.spread(&projection.origin, -projection.strength, ctx);
+        }
+    }
+
+    pub fn set_ambient(&mut self, ambient: f64) {
+        self.ambient = ambient;
+    }
+
+    /// Brings the layer up to date with the given world. Only sources which have appeared,
+    /// disappeared, moved, or changed strength are reprojected. Returns the number reprojected.
+    pub fn refresh(&mut self, world: &World) -> usize {
+        let current = self.gather(world);
+        let ctx = world.ctx();
+        let mut reprojected = 0;
+
+        let departed: Vec<Source> = self.projections.keys()
+            .filter(|source| !current.contains_key(source))
+            .cloned()
+            .collect();
+        for source in departed {
+            self.withdraw(&source, ctx);
+            reprojected += 1;
+        }
+
+        for (source, projection) in current {
+            if self.projections.get(&source) != Some(&projection) {
+                self.project(source, projection.origin, projection.strength, ctx);
+                reprojected += 1;
+            }
+        }
+
+        self.ambient = self.gather_ambient(world);
+
+        reprojected
+    }
+
+    /// Clears all influence from the layer
+    pub fn clear(&mut self) {
+        self.projections.clear();
+        self.values.clear();
+        self.ambient = 0.0;
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Helper Methods    *
+    \*  *  *  *  *  *  *  */
+
+    /// Adds the given strength, decayed by distance, to every cell within radius of the given origin
+    fn spread(&mut self, origin: &coords::Position, strength: f64, ctx: &Context) {
+        for pos in origin.within_radius(self.radius, ctx) {
+            let influence = strength * self.decay.factor(origin.distance_to(&pos), self.radius);
+            let value = self.values.entry(pos).or_insert(0.0);
+            *value += influence;
+
+            // Keep the map sparse as influence is withdrawn
+            if value.abs() < INFLUENCE_EPSILON {
+                self.values.remove(&pos);
+            }
+        }
+    }
+
+    /// Determines the projections the layer's emitters currently call for
+    fn gather(&self, world: &World) -> HashMap<Source, Projection> {
+        let mut projections = HashMap::new();
+
+        for emitter in self.emitters.iter() {
+            match *emitter {
+                Emitter::Hostiles(viewpoint)    => {
+                    gather_actors(world, &viewpoint, Relationship::Hostile, &mut projections);
+                },
+                Emitter::Allies(viewpoint)      => {
+                    gather_actors(world, &viewpoint, Relationship::Allied, &mut projections);
+                },
+                Emitter::Resources(element)     => {
+                    let resources = world.resources().iter()
+                        .filter(|resource| resource.state() != State::Depleted)
+                        .filter(|resource| element.is_none_or(|element| resource.element() == element));
+                    for resource in resources {
+                        projections.insert(Source::Resource(*resource.uid()), Projection {
+                            origin:     *resource.origin(),
+                            strength:   resource.state() as u8 as f64 / State::Overflow as u8 as f64,
+                        });
+                    }
+                },
+                Emitter::Weather(_)             => {},
+            }
+        }
+
+        projections
+    }
+
+    /// Determines the ambient influence of the weather, if the layer listens to it
+    fn gather_ambient(&self, world: &World) -> f64 {
+        let weather = world.weather();
+        let intensity = f64::clamp(weather.intensity_exact(world.tick()) / MAX_WEATHER_INTENSITY, 0.0, 1.0);
+
+        self.emitters.iter()
+            .filter(|emitter| match emitter {
+                Emitter::Weather(element)   => element.is_none_or(|element| weather.element() == element),
+                _                           => false,
+            })
+            .map(|_| intensity)
+            .sum()
+    }
+}
+
+impl InfluenceMap {
+    /// Constructs a map with no layers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.get(name)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn add_layer(&mut self, name: &str, layer: Layer) {
+        self.layers.insert(name.to_string(), layer);
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        self.layers.get_mut(name)
+    }
+
+    pub fn remove_layer(&mut self, name: &str) -> Option<Layer> {
+        self.layers.remove(name)
+    }
+
+    /// Brings every layer up to date with the given world, returning the number of sources reprojected
+    pub fn refresh(&mut self, world: &World) -> usize {
+        self.layers.values_mut().map(|layer| layer.refresh(world)).sum()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the weighted combination of the layers' influence at the given position.
+    /// Layers named by the goal but absent from the map contribute nothing.
+    pub fn value(&self, pos: &coords::Position, goal: &Goal) -> f64 {
+        goal.weights.iter()
+            .filter_map(|(name, weight)| self.layers.get(name).map(|layer| weight * layer.value(pos)))
+            .sum()
+    }
+
+    /// Finds the cell within the given radius of the given origin that best serves the goal, along
+    /// with its value. Only cells which may be entered and are unoccupied (save the origin) are
+    /// considered. Ties are broken in favour of nearer cells.
+    pub fn best_nearby(&self,
+                       world:   &World,
+                       origin:  &coords::Position,
+                       radius:  usize,
+                       goal:    &Goal) -> Option<(coords::Position, f64)> {
+        let mut best: Option<(coords::Position, f64)> = None;
+
+        for pos in origin.within_radius(radius, world.ctx()) {
+            if pos != *origin && (world.is_occupied(&pos) || movement::step_cost(world, &pos).is_none()) {
+                continue;
+            }
+
+            let value = self.value(&pos, goal);
+            let improves = match best {
+                Some((best_pos, best_value))    => {
+                    value > best_value ||
+                    (value == best_value && origin.distance_to(&pos) < origin.distance_to(&best_pos))
+                },
+                None                            => true,
+            };
+            if improves {
+                best = Some((pos, value));
+            }
+        }
+
+        best
+    }
+}
+
+impl Goal {
+    /// Constructs a goal with no weights
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn with_weight(mut self, layer: &str, weight: f64) -> Self {
+        self.weights.push((layer.to_string(), weight));
+
+        self
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Collects projections for every undefeated actor bearing the given relationship to the viewpoint actor
+fn gather_actors(world:         &World,
+                 viewpoint:     &[u8; 16],
+                 relationship:  Relationship,
+                 projections:   &mut HashMap<Source, Projection>) {
+    let actors = world.actors().iter()
+        .filter(|actor| actor.uid() != viewpoint && !actor.is_defeated())
+        .filter(|actor| faction::relationship(world, viewpoint, actor.uid()) == relationship);
+
+    for actor in actors {
+        let strength = if actor.max_health() == 0 {
+            0.0
+        } else {
+            actor.cur_health() as f64 / actor.max_health() as f64
+        };
+
+        projections.insert(Source::Actor(*actor.uid()), Projection {
+            origin: *actor.origin(),
+            strength,
+        });
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        actor::{
+            Actor,
+            faction::Relations,
+        },
+        coords::tests::pos,
+        hex_directions,
+        mechanics::{
+            resource::Resource,
+            weather,
+        },
+        polyfunc::PolyFunc,
+    };
+
+    #[test]
+    fn decay_and_goals() {
+        let world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        assert_eq!(Decay::Linear.factor(0, 3), 1.0);
+        assert_eq!(Decay::Linear.factor(2, 3), 0.5);
+        assert_eq!(Decay::Linear.factor(4, 3), 0.0);
+        assert_eq!(Decay::Exponential(0.5).factor(2, 3), 0.25);
+
+        let mut danger = Layer::new(Vec::new(), Decay::Linear, 3);
+        danger.project(Source::Actor([1; 16]), pos(0, 0, 0, &ctx), 1.0, &ctx);
+        let mut power = Layer::new(Vec::new(), Decay::Linear, 3);
+        power.project(Source::Resource([2; 16]), pos(3, 0, -3, &ctx), 1.0, &ctx);
+
+        let mut map = InfluenceMap::new();
+        map.add_layer("danger", danger);
+        map.add_layer("power", power);
+
+        // Seeking power regardless of danger heads straight for the resource
+        let greedy = Goal::new().with_weight("power", 1.0);
+        assert_eq!(map.best_nearby(&world, &pos(1, 0, -1, &ctx), 2, &greedy), Some((pos(3, 0, -3, &ctx), 1.0)));
+
+        // Fleeing danger heads as far from the actor as possible
+        let cautious = Goal::new().with_weight("danger", -1.0);
+        let (best, value) = map.best_nearby(&world, &pos(1, 0, -1, &ctx), 2, &cautious).unwrap();
+        assert_eq!(value, -0.25);
+        assert_eq!(best.distance_to(&pos(0, 0, 0, &ctx)), 3);
+
+        assert_eq!(map.value(&pos(1, 0, -1, &ctx), &greedy.with_weight("danger", -1.0)), 0.5 - 0.75);
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let mut relations = Relations::new();
+        relations.set("Crown", "Bandits", Relationship::Hostile);
+        world.set_relations(relations);
+
+        let mut knight = Actor::new("Knight", pos(0, 0, 0, &ctx), 0, Vec::new());
+        knight.set_faction(Some("Crown"));
+        let knight_uid = *knight.uid();
+        let mut bandit = Actor::new("Bandit", pos(4, 0, -4, &ctx), 0, Vec::new());
+        bandit.set_faction(Some("Bandits"));
+        let bandit_uid = *bandit.uid();
+        world.add_actor(knight);
+        world.add_actor(bandit);
+
+        let mut danger = Layer::new(vec![Emitter::Hostiles(knight_uid)], Decay::Linear, 2);
+        assert_eq!(danger.refresh(&world), 1);
+        assert_eq!(danger.sources(), vec![Source::Actor(bandit_uid)]);
+        assert_eq!(danger.value(&pos(4, 0, -4, &ctx)), 1.0);
+        assert_eq!(danger.value(&pos(1, 0, -1, &ctx)), 0.0);
+
+        // Nothing has changed
+        assert_eq!(danger.refresh(&world), 0);
+
+        // Only the bandit is reprojected once it moves
+        for _ in 0..3 {
+            world.actor_mut(&bandit_uid).unwrap().move_one_cell(hex_directions::Side::SouthWest, &ctx).unwrap();
+        }
+        assert_eq!(danger.refresh(&world), 1);
+        assert_eq!(danger.value(&pos(4, 0, -4, &ctx)), 0.0);
+        assert_eq!(danger.value(&pos(1, 0, -1, &ctx)), 1.0);
+    }
+
+    #[test]
+    fn resources_and_weather() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        world.set_weather(weather::Event::new(Element::Fire, PolyFunc::new(255.0, 100.0, 0.0)));
+        world.advance(std::time::Duration::from_secs(50));
+        world.add_resource(Resource::new(Element::Fire, State::Overflow, pos(0, 0, 0, &ctx), 1));
+        world.add_resource(Resource::new(Element::Water, State::Full, pos(0, 0, 0, &ctx), 1));
+
+        let mut fire = Layer::new(vec![Emitter::Resources(Some(Element::Fire)), Emitter::Weather(Some(Element::Fire))], Decay::Exponential(0.5), 2);
+        fire.refresh(&world);
+        assert!((fire.ambient() - 1.0).abs() < 1e-9);
+        assert!((fire.value(&pos(0, 0, 0, &ctx)) - 2.0).abs() < 1e-9);
+        assert!((fire.value(&pos(1, 0, -1, &ctx)) - 1.5).abs() < 1e-9);
+
+        let mut water = Layer::new(vec![Emitter::Resources(Some(Element::Water)), Emitter::Weather(Some(Element::Water))], Decay::Linear, 2);
+        water.refresh(&world);
+        assert_eq!(water.ambient(), 0.0);
+        assert_eq!(water.value(&pos(0, 0, 0, &ctx)), 0.8);
+    }
+}
@@ -0,0 +1,501 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ai/mcts.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines a Monte Carlo tree search planner for an actor.
+
+    Each iteration clones the world and walks the search tree from the root,
+    choosing actions by upper confidence bound until reaching a node with
+    untried actions. One such action is expanded, after which the sequence is
+    completed with random actions out to the planning horizon. The resulting
+    world is evaluated by the harm done to the actor's hostiles less the harm
+    done to its allies (itself included), and the value propagated back up
+    the tree.
+
+    Only the planning actor acts during simulation, with the game clock
+    advancing a fixed step after each of its actions.
+
+    Searching stops once either the iteration or the time budget is spent. The
+    planner's RNG is seeded, so that searches bounded only by iterations are
+    reproducible.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use crate::{
+    ability::cast::Target,
+    actor::faction::{
+        self,
+        Relationship,
+    },
+    hex_directions,
+    mechanics::movement,
+    turn::{
+        self,
+        Action,
+    },
+    world::World,
+};
+
+use super::utility;
+
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default maximum number of search iterations
+const DEFAULT_ITERATIONS: usize = 1000;
+
+/// Default maximum span of real time spent searching (in milliseconds)
+const DEFAULT_TIME_BUDGET_MS: u64 = 100;
+
+/// Default number of actions in each simulated sequence
+const DEFAULT_HORIZON: usize = 4;
+
+/// Default exploration constant of the upper confidence bound
+const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+/// Default span of game time that passes after each simulated action (in seconds)
+const DEFAULT_STEP_SECS: u64 = 6;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Monte Carlo tree search planner
+#[derive(Debug, Clone, PartialEq)]
+pub struct Planner {
+    iterations:     usize,
+    time_budget:    Duration,
+    horizon:        usize,      // Number of actions in each simulated sequence
+    exploration:    f64,
+    step:           Duration,   // Game time that passes after each simulated action
+    seed:           u64,
+}
+
+/// Statistics gathered for a single action available at the root
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub action: Action,
+    pub visits: u32,
+    pub value:  f64,            // Mean value of the simulations passing through the action
+}
+
+/// The result of a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub action:     Action,
+    pub value:      f64,
+    pub iterations: usize,
+    pub candidates: Vec<Candidate>,
+}
+
+/// A single node of the search tree
+struct Node {
+    action:     Option<Action>,     // Action leading to this node; None at the root
+    parent:     Option<usize>,
+    children:   Vec<usize>,
+    untried:    Vec<Action>,
+    visits:     u32,
+    total:      f64,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Planner {
+    /// Constructs a planner with the default budgets
+    pub fn new() -> Self {
+        Self {
+            iterations:     DEFAULT_ITERATIONS,
+            time_budget:    Duration::from_millis(DEFAULT_TIME_BUDGET_MS),
+            horizon:        DEFAULT_HORIZON,
+            exploration:    DEFAULT_EXPLORATION,
+            step:           Duration::from_secs(DEFAULT_STEP_SECS),
+            seed:           0,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+
+        self
+    }
+
+    pub fn with_time_budget(mut self, time_budget: Duration) -> Self {
+        self.time_budget = time_budget;
+
+        self
+    }
+
+    pub fn with_horizon(mut self, horizon: usize) -> Self {
+        self.horizon = horizon;
+
+        self
+    }
+
+    pub fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+
+        self
+    }
+
+    pub fn with_step(mut self, step: Duration) -> Self {
+        self.step = step;
+
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+
+        self
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    pub fn time_budget(&self) -> Duration {
+        self.time_budget
+    }
+
+    pub fn horizon(&self) -> usize {
+        self.horizon
+    }
+
+    pub fn exploration(&self) -> f64 {
+        self.exploration
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Searches for the best action available to the given actor.
+    /// Returns None if the actor is unknown or has no actions available, or if the iteration limit
+    /// or time budget is exhausted before a single iteration can begin, as with a zero budget.
+    pub fn plan(&self, world: &World, actor_uid: &[u8; 16]) -> Option<Plan> {
+        let root_actions = candidates(world, actor_uid);
+        if root_actions.is_empty() {
+            return None;
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut tree = vec![Node::new(None, None, root_actions)];
+        let start = Instant::now();
+        let mut iterations = 0;
+
+        while iterations < self.iterations && start.elapsed() < self.time_budget {
+            let mut state = world.clone();
+            let mut node = 0;
+            let mut depth = 0;
+
+            // Selection
+            while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+                node = self.select(&tree, node);
+                let action = tree[node].action.clone().unwrap();
+                self.simulate(&mut state, actor_uid, &action);
+                depth += 1;
+            }
+
+            // Expansion
+            if !tree[node].untried.is_empty() && depth < self.horizon {
+                let idx = rng.gen_range(0, tree[node].untried.len());
+                let action = tree[node].untried.swap_remove(idx);
+                let performed = self.simulate(&mut state, actor_uid, &action);
+                depth += 1;
+
+                let untried = if performed { candidates(&state, actor_uid) } else { Vec::new() };
+                tree.push(Node::new(Some(action), Some(node), untried));
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+
+            // Rollout
+            while depth < self.horizon {
+                let actions = candidates(&state, actor_uid);
+                if actions.is_empty() {
+                    break;
+                }
+                let action = &actions[rng.gen_range(0, actions.len())];
+                if !self.simulate(&mut state, actor_uid, action) {
+                    break;
+                }
+                depth += 1;
+            }
+
+            // Backpropagation
+            let value = evaluate(world, &state, actor_uid);
+            let mut cur = Some(node);
+            while let Some(idx) = cur {
+                tree[idx].visits += 1;
+                tree[idx].total += value;
+                cur = tree[idx].parent;
+            }
+
+            iterations += 1;
+        }
+
+        let candidates: Vec<Candidate> = tree[0].children.iter()
+            .map(|&child| Candidate {
+                action: tree[child].action.clone().unwrap(),
+                visits: tree[child].visits,
+                value:  tree[child].mean(),
+            })
+            .collect();
+
+        // Prefer the most thoroughly explored action, then the most valuable
+        let best = candidates.iter()
+            .max_by(|a, b| a.visits.cmp(&b.visits).then(a.value.total_cmp(&b.value)))?;
+
+        Some(Plan {
+            action: best.action.clone(),
+            value:  best.value,
+            iterations,
+            candidates: candidates.clone(),
+        })
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Helper Methods    *
+    \*  *  *  *  *  *  *  */
+
+    /// Selects the child of the given node with the greatest upper confidence bound
+    fn select(&self, tree: &[Node], node: usize) -> usize {
+        let parent_visits = tree[node].visits.max(1) as f64;
+
+        tree[node].children.iter()
+            .cloned()
+            .max_by(|&a, &b| {
+                let bound = |idx: usize| {
+                    let child = &tree[idx];
+                    child.mean() + self.exploration * (parent_visits.ln() / child.visits.max(1) as f64).sqrt()
+                };
+                bound(a).total_cmp(&bound(b))
+            })
+            .unwrap()
+    }
+
+    /// Performs the given action within the simulated world, then advances the clock.
+    /// Returns false if the action could not be performed.
+    fn simulate(&self, state: &mut World, actor_uid: &[u8; 16], action: &Action) -> bool {
+        let performed = turn::perform(state, actor_uid, action).is_ok();
+        state.advance(self.step);
+
+        performed
+    }
+}
+
+impl Node {
+    fn new(action: Option<Action>, parent: Option<usize>, untried: Vec<Action>) -> Self {
+        Self {
+            action,
+            parent,
+            children:   Vec::new(),
+            untried,
+            visits:     0,
+            total:      0.0,
+        }
+    }
+
+    /// Mean value of the simulations passing through this node
+    fn mean(&self) -> f64 {
+        if self.visits == 0 {
+            return 0.0;
+        }
+
+        self.total / self.visits as f64
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumerates the actions available to the given actor: a step in any passable direction,
+/// or a cast of any known ability at any actor it could currently be cast at
+pub fn candidates(world: &World, actor_uid: &[u8; 16]) -> Vec<Action> {
+    let actor = match world.actor(actor_uid) {
+        Some(actor) if actor.can_act()  => actor,
+        _                               => return Vec::new(),
+    };
+
+    let mut actions: Vec<Action> = hex_directions::Provider::new(hex_directions::Side::SouthEast)
+        .filter(|dir| movement::move_cost(world, actor_uid, *dir).is_ok())
+        .map(Action::Move)
+        .collect();
+
+    for ability in actor.abilities() {
+        for target in world.actors().iter().filter(|target| !target.is_defeated()) {
+            if utility::can_cast(world, actor, ability, target) {
+                actions.push(Action::Cast(*ability.uid(), Target::Actor(*target.uid())));
+            }
+        }
+    }
+
+    actions
+}
+
+/// Evaluates the given simulated world against the world it was simulated from, from the
+/// perspective of the given actor. Each hostile's lost fraction of health counts in its favour,
+/// while each ally's (its own included) counts against.
+pub fn evaluate(origin: &World, state: &World, actor_uid: &[u8; 16]) -> f64 {
+    origin.actors().iter()
+        .filter_map(|before| state.actor(before.uid()).map(|after| (before, after)))
+        .map(|(before, after)| {
+            let lost = (before.cur_health() as f64 - after.cur_health() as f64) / before.max_health().max(1) as f64;
+
+            match faction::relationship(origin, actor_uid, before.uid()) {
+                Relationship::Hostile   => lost,
+                Relationship::Allied    => -lost,
+                Relationship::Neutral   => 0.0,
+            }
+        })
+        .sum()
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for Planner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ability::{
+            Ability,
+            aspect::*,
+        },
+        actor::{
+            Actor,
+            faction::Relations,
+        },
+        context::Context,
+        coords::tests::pos,
+        element::Element,
+    };
+
+    #[test]
+    fn reproducible_plans() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let mut relations = Relations::new();
+        relations.set("Crown", "Bandits", Relationship::Hostile);
+        world.set_relations(relations);
+
+        let smite = Ability::new("Smite", 30, Aspects::new(Aesthetics::Impressive, Element::Light, Method::Staff, Morality::Neutral, School::Destruction))
+            .with_range(2)
+            .with_fatigue_cost(10);
+        let smite_uid = *smite.uid();
+
+        let mut knight = Actor::new("Knight", pos(0, 0, 0, &ctx), 0, vec![smite]);
+        knight.set_faction(Some("Crown"));
+        let knight_uid = *knight.uid();
+        let mut squire = Actor::new("Squire", pos(-1, 1, 0, &ctx), 0, Vec::new());
+        squire.set_faction(Some("Crown"));
+        let mut bandit = Actor::new("Bandit", pos(2, 0, -2, &ctx), 0, Vec::new());
+        bandit.set_faction(Some("Bandits"));
+        let bandit_uid = *bandit.uid();
+        world.add_actor(knight);
+        world.add_actor(squire);
+        world.add_actor(bandit);
+
+        let planner = Planner::new()
+            .with_iterations(300)
+            .with_time_budget(Duration::from_secs(60))
+            .with_horizon(3)
+            .with_seed(7);
+        let plan = planner.plan(&world, &knight_uid).unwrap();
+        assert_eq!(plan.iterations, 300);
+        assert_eq!(plan.action, Action::Cast(smite_uid, Target::Actor(bandit_uid)));
+        assert!(plan.value > 0.0);
+
+        // The same seed yields the same search
+        assert_eq!(planner.plan(&world, &knight_uid), Some(plan));
+
+        // Planning leaves the world untouched
+        assert_eq!(world.actor(&bandit_uid).unwrap().cur_health(), world.actor(&bandit_uid).unwrap().max_health());
+    }
+
+    #[test]
+    fn budgets() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let actor = Actor::new("Wanderer", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let uid = *actor.uid();
+        world.add_actor(actor);
+
+        // No time, no search
+        let plan = Planner::new().with_time_budget(Duration::from_secs(0)).plan(&world, &uid);
+        assert_eq!(plan, None);
+
+        let plan = Planner::new().with_iterations(10).with_time_budget(Duration::from_secs(60)).plan(&world, &uid).unwrap();
+        assert_eq!(plan.iterations, 10);
+        assert_eq!(plan.candidates.iter().map(|candidate| candidate.visits).sum::<u32>(), 10);
+
+        assert_eq!(Planner::new().plan(&world, &[0; 16]), None);
+    }
+}
@@ -0,0 +1,682 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : ai/utility.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines a utility-based AI for choosing an actor's next action.
+
+    Each turn, the candidate actions available to the actor are enumerated:
+    casting each known ability at each valid target, stepping towards each
+    resource, retreating from the nearest hostile, or waiting. Each candidate
+    is scored by the considerations of its behaviour, which map an input
+    (normalized to [0, 1]) through a response curve. The outputs are
+    multiplied together, compensated for the number of considerations, and
+    scaled by the behaviour's weight.
+
+    Profiles of behaviours are plain data, and may be loaded from JSON. Every
+    decision records the factors that produced its score for debugging.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::fmt;
+
+use crate::{
+    ability::{
+        Ability,
+        cast::{
+            self,
+            EffectKind,
+            Target,
+        },
+        modifier::{
+            Circumstances,
+            Resolution,
+        },
+    },
+    actor::{
+        Actor,
+        faction::{
+            self,
+            Relationship,
+        },
+        fatigue,
+    },
+    coords,
+    element::Elemental,
+    hex_directions,
+    mechanics::{
+        movement,
+        resource::{
+            Resource,
+            State,
+        },
+    },
+    turn::Action,
+    world::World,
+    Plottable,
+};
+
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Distance (in cells) at and beyond which all distances are considered equally far
+const MAX_CONSIDERED_DISTANCE: f64 = 10.0;
+
+/// Benefit of affecting an actor of a neutral faction
+const NEUTRAL_BENEFIT: f64 = 0.25;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the inputs a consideration may take, each normalized to [0, 1]
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Input {
+    CasterHealth,       // Fraction of the caster's health remaining
+    CasterFatigue,      // Fraction of the caster's fatigue capacity spent
+    TargetHealth,       // Fraction of the target's health remaining
+    TargetDistance,     // Distance to the target
+    ThreatDistance,     // Distance to the nearest hostile actor
+    Benefit,            // Desirability of the ability's effect upon the target, given their relationship
+    Potency,            // Expected magnitude of the ability relative to the target's health
+    ResourceDistance,   // Distance to the edge of the resource's radius
+    ResourceState,      // Fullness of the resource
+    Affinity,           // Fraction of the caster's abilities sharing the resource's element
+}
+
+/// Enumeration of the response curves mapping a consideration's input to its output.
+/// Outputs are clamped to [0, 1].
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Curve {
+    Linear(f64, f64),       // Slope and intercept
+    Power(f64),             // Exponent
+    Logistic(f64, f64),     // Steepness and midpoint
+    Step(f64),              // Threshold at and above which the output is 1
+    Constant(f64),
+}
+
+/// A single input to a behaviour's score, and the curve through which it is considered
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Consideration {
+    pub name:   String,
+    pub input:  Input,
+    pub curve:  Curve,
+}
+
+/// A weighted set of considerations scoring one kind of action
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Behaviour {
+    pub weight:         f64,
+    pub considerations: Vec<Consideration>,
+}
+
+/// The behaviours an actor weighs when deciding upon its next action
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Profile {
+    pub cast:       Behaviour,
+    pub approach:   Behaviour,
+    pub retreat:    Behaviour,
+    pub wait:       Behaviour,
+}
+
+/// Enumeration of the purposes behind a candidate action
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Intent {
+    Cast([u8; 16], [u8; 16]),   // Ability, and the actor it is cast at
+    Approach([u8; 16]),         // Resource approached
+    Retreat([u8; 16]),          // Hostile actor retreated from
+    Wait,
+}
+
+/// Everything a consideration may draw its input from
+#[derive(Copy, Clone)]
+pub struct Situation<'a> {
+    pub world:      &'a World,
+    pub caster:     &'a Actor,
+    pub ability:    Option<&'a Ability>,
+    pub target:     Option<&'a Actor>,
+    pub resource:   Option<&'a Resource>,
+    pub threat:     Option<&'a Actor>,
+}
+
+/// Record of a single consideration's contribution to a score
+#[derive(Debug, Clone, PartialEq)]
+pub struct Factor {
+    pub consideration:  String,
+    pub input:          f64,
+    pub output:         f64,
+}
+
+/// A scored candidate action, along with the factors that produced its score
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    pub intent:     Intent,
+    pub action:     Option<Action>,     // None when waiting
+    pub score:      f64,
+    pub factors:    Vec<Factor>,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Input {
+    /// Evaluates the input for the given situation
+    pub fn evaluate(&self, situation: &Situation) -> f64 {
+        let caster = situation.caster;
+
+        match *self {
+            Input::CasterHealth     => health_fraction(caster),
+            Input::CasterFatigue    => *caster.cur_fatigue() as f64 / u8::MAX as f64,
+            Input::TargetHealth     => situation.target.map_or(1.0, health_fraction),
+            Input::TargetDistance   => {
+                situation.target.map_or(1.0, |target| normalize_distance(caster.origin().distance_to(target.origin())))
+            },
+            Input::ThreatDistance   => {
+                situation.threat.map_or(1.0, |threat| normalize_distance(caster.origin().distance_to(threat.origin())))
+            },
+            Input::Benefit          => match (situation.ability, situation.target) {
+                (Some(ability), Some(target))   => benefit(situation.world, caster, ability, target),
+                _                               => 0.0,
+            },
+            Input::Potency          => match (situation.ability, situation.target) {
+                (Some(ability), Some(target))   => potency(situation.world, caster, ability, target),
+                _                               => 0.0,
+            },
+            Input::ResourceDistance => situation.resource.map_or(1.0, |resource| {
+                let distance = caster.origin().distance_to(resource.origin()) as usize;
+                normalize_distance(distance.saturating_sub(resource.radius()) as u32)
+            }),
+            Input::ResourceState    => {
+                situation.resource.map_or(0.0, |resource| resource.state() as u8 as f64 / State::Overflow as u8 as f64)
+            },
+            Input::Affinity         => situation.resource.map_or(0.0, |resource| {
+                if caster.abilities().is_empty() {
+                    return 0.0;
+                }
+
+                let matching = caster.abilities().iter()
+                    .filter(|ability| *ability.element() == resource.element())
+                    .count();
+                matching as f64 / caster.abilities().len() as f64
+            }),
+        }
+    }
+}
+
+impl Curve {
+    /// Maps the given input to an output in [0, 1]
+    pub fn respond(&self, input: f64) -> f64 {
+        let output = match *self {
+            Curve::Linear(slope, intercept)     => slope * input + intercept,
+            Curve::Power(exponent)              => input.max(0.0).powf(exponent),
+            Curve::Logistic(steepness, midpoint) => 1.0 / (1.0 + (-steepness * (input - midpoint)).exp()),
+            Curve::Step(threshold)              => if input >= threshold { 1.0 } else { 0.0 },
+            Curve::Constant(value)              => value,
+        };
+
+        output.clamp(0.0, 1.0)
+    }
+}
+
+impl Consideration {
+    /// Fully-qualified constructor
+    pub fn new(name: &str, input: Input, curve: Curve) -> Self {
+        Self {
+            name: name.to_string(),
+            input,
+            curve,
+        }
+    }
+}
+
+impl Behaviour {
+    /// Fully-qualified constructor
+    pub fn new(weight: f64, considerations: Vec<Consideration>) -> Self {
+        Self {weight, considerations}
+    }
+
+    /// Scores the given situation, returning the score and the factors that produced it
+    pub fn score(&self, situation: &Situation) -> (f64, Vec<Factor>) {
+        let mut product = 1.0;
+        let mut factors = Vec::new();
+
+        for consideration in self.considerations.iter() {
+            let input = consideration.input.evaluate(situation);
+            let output = consideration.curve.respond(input);
+            product *= output;
+
+            factors.push(Factor {
+                consideration: consideration.name.clone(),
+                input,
+                output,
+            });
+        }
+
+        // Compensate for the number of considerations, so that behaviours weighing many are not penalized for it
+        if !factors.is_empty() {
+            let modification = 1.0 - 1.0 / factors.len() as f64;
+            let make_up = (1.0 - product) * modification;
+            product += make_up * product;
+        }
+
+        (self.weight * product, factors)
+    }
+}
+
+impl Profile {
+    /// Constructs a profile from the given JSON
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Scores every candidate action available to the given actor, best first.
+/// Actors that are unknown or unable to act have no candidates.
+pub fn evaluate(world: &World, actor_uid: &[u8; 16], profile: &Profile) -> Vec<Decision> {
+    let caster = match world.actor(actor_uid) {
+        Some(actor) if actor.can_act()  => actor,
+        _                               => return Vec::new(),
+    };
+
+    let threat = nearest_hostile(world, caster);
+    let base = Situation {
+        world,
+        caster,
+        ability:    None,
+        target:     None,
+        resource:   None,
+        threat,
+    };
+
+    let mut decisions = Vec::new();
+
+    // Cast each known ability at each valid target
+    for ability in caster.abilities() {
+        for target in world.actors().iter().filter(|actor| !actor.is_defeated()) {
+            if !can_cast(world, caster, ability, target) {
+                continue;
+            }
+
+            let situation = Situation {ability: Some(ability), target: Some(target), ..base};
+            decisions.push(decision(&profile.cast,
+                                    &situation,
+                                    Intent::Cast(*ability.uid(), *target.uid()),
+                                    Some(Action::Cast(*ability.uid(), Target::Actor(*target.uid())))));
+        }
+    }
+
+    // Step towards each resource not already within reach
+    let resources = world.resources().iter()
+        .filter(|resource| resource.state() != State::Depleted)
+        .filter(|resource| !resource.covers(caster.origin()));
+    for resource in resources {
+        let goal = resource.origin();
+        if let Some(dir) = best_step(world, caster, |pos| -(pos.distance_to(goal) as i64)) {
+            let situation = Situation {resource: Some(resource), ..base};
+            decisions.push(decision(&profile.approach, &situation, Intent::Approach(*resource.uid()), Some(Action::Move(dir))));
+        }
+    }
+
+    // Step away from the nearest hostile
+    if let Some(threat) = threat {
+        let danger = threat.origin();
+        if let Some(dir) = best_step(world, caster, |pos| pos.distance_to(danger) as i64) {
+            decisions.push(decision(&profile.retreat, &base, Intent::Retreat(*threat.uid()), Some(Action::Move(dir))));
+        }
+    }
+
+    decisions.push(decision(&profile.wait, &base, Intent::Wait, None));
+
+    decisions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    decisions
+}
+
+/// Decides upon the best action available to the given actor, if any
+pub fn decide(world: &World, actor_uid: &[u8; 16], profile: &Profile) -> Option<Decision> {
+    evaluate(world, actor_uid, profile).into_iter().next()
+}
+
+/// Scores a single candidate action
+fn decision(behaviour: &Behaviour, situation: &Situation, intent: Intent, action: Option<Action>) -> Decision {
+    let (score, factors) = behaviour.score(situation);
+
+    Decision {intent, action, score, factors}
+}
+
+/// Determines if the given caster could cast the given ability at the given actor right now
+pub(crate) fn can_cast(world: &World, caster: &Actor, ability: &Ability, target: &Actor) -> bool {
+    cast::validate(world, caster, ability, &Target::Actor(*target.uid())).is_ok()
+        && caster.can_exert(fatigue::cost(ability)).is_ok()
+        && expected_resolution(world, caster, ability).blocked_by.is_none()
+}
+
+/// Resolves the aspect rules applicable to the given caster's use of the given ability
+fn expected_resolution(world: &World, caster: &Actor, ability: &Ability) -> Resolution {
+    world.aspect_rules().resolve(ability.aspects(), &Circumstances::from(caster))
+}
+
+/// Desirability of the ability's effect upon the target: healing allies and harming hostiles are desirable
+fn benefit(world: &World, caster: &Actor, ability: &Ability, target: &Actor) -> f64 {
+    let heals = expected_resolution(world, caster, ability).kind == EffectKind::Heal;
+
+    match (heals, faction::relationship(world, caster.uid(), target.uid())) {
+        (true, Relationship::Allied)    |
+        (false, Relationship::Hostile)  => 1.0,
+        (_, Relationship::Neutral)      => NEUTRAL_BENEFIT,
+        _                               => 0.0,
+    }
+}
+
+/// Expected magnitude of the ability relative to the health it may remove from or restore to the target
+fn potency(world: &World, caster: &Actor, ability: &Ability, target: &Actor) -> f64 {
+    let resolution = expected_resolution(world, caster, ability);
+    let magnitude = ability.potency() as f64
+                  * caster.exhaustion().potency_modifier()
+                  * resolution.potency_multiplier;
+
+    let headroom = match resolution.kind {
+        EffectKind::Heal    => target.max_health() - target.cur_health(),
+        _                   => target.cur_health(),
+    };
+    if headroom == 0 {
+        return 0.0;
+    }
+
+    f64::min(magnitude / headroom as f64, 1.0)
+}
+
+/// Finds the nearest undefeated actor hostile to the given actor
+pub(crate) fn nearest_hostile<'a>(world: &'a World, actor: &Actor) -> Option<&'a Actor> {
+    faction::hostiles_within(world, actor.uid(), usize::MAX).iter()
+        .filter_map(|uid| world.actor(uid))
+        .min_by_key(|hostile| actor.origin().distance_to(hostile.origin()))
+}
+
+/// Chooses the single step which most improves the given valuation of the actor's position,
+/// preferring cheaper steps among equals. Returns None if no step improves upon standing still.
+pub(crate) fn best_step<F>(world: &World, actor: &Actor, value: F) -> Option<hex_directions::Side>
+where F: Fn(&coords::Position) -> i64 {
+    let mut best: Option<(hex_directions::Side, i64, u32)> = None;
+
+    for dir in hex_directions::Provider::new(hex_directions::Side::SouthEast) {
+        let cost = match movement::move_cost(world, actor.uid(), dir) {
+            Ok(cost)    => cost,
+            Err(_)      => continue,
+        };
+
+        let mut dest = *actor.origin();
+        if dest.translate(&coords::Translation::from(dir), world.ctx()).is_err() {
+            continue;
+        }
+
+        let dest_value = value(&dest);
+        let improves = match best {
+            Some((_, best_value, best_cost))    => dest_value > best_value || (dest_value == best_value && cost < best_cost),
+            None                                => true,
+        };
+        if improves {
+            best = Some((dir, dest_value, cost));
+        }
+    }
+
+    best.filter(|&(_, best_value, _)| best_value > value(actor.origin()))
+        .map(|(dir, _, _)| dir)
+}
+
+/// Fraction of the given actor's health remaining
+fn health_fraction(actor: &Actor) -> f64 {
+    if actor.max_health() == 0 {
+        return 0.0;
+    }
+
+    actor.cur_health() as f64 / actor.max_health() as f64
+}
+
+/// Normalizes the given distance to [0, 1]
+fn normalize_distance(distance: u32) -> f64 {
+    f64::min(distance as f64 / MAX_CONSIDERED_DISTANCE, 1.0)
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+/*  *  *  *  *  *  *  *\
+ *      Profile       *
+\*  *  *  *  *  *  *  */
+impl Default for Profile {
+    /// Constructs a profile which favours harming hostiles, gathers power when idle,
+    /// and retreats from danger once tired
+    fn default() -> Self {
+        Self {
+            cast:       Behaviour::new(1.0, vec![
+                Consideration::new("Benefit",       Input::Benefit,         Curve::Linear(1.0, 0.0)),
+                Consideration::new("Potency",       Input::Potency,         Curve::Linear(0.6, 0.4)),
+                Consideration::new("Fatigue",       Input::CasterFatigue,   Curve::Linear(-0.5, 1.0)),
+            ]),
+            approach:   Behaviour::new(0.5, vec![
+                Consideration::new("Proximity",     Input::ResourceDistance, Curve::Linear(-0.8, 1.0)),
+                Consideration::new("Fullness",      Input::ResourceState,   Curve::Linear(0.8, 0.2)),
+                Consideration::new("Affinity",      Input::Affinity,        Curve::Linear(0.7, 0.3)),
+            ]),
+            retreat:    Behaviour::new(1.0, vec![
+                Consideration::new("Fatigue",       Input::CasterFatigue,   Curve::Logistic(12.0, 0.6)),
+                Consideration::new("Threat",        Input::ThreatDistance,  Curve::Linear(-1.0, 1.0)),
+            ]),
+            wait:       Behaviour::new(0.1, Vec::new()),
+        }
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *       Intent       *
+\*  *  *  *  *  *  *  */
+impl fmt::Display for Intent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Intent::Cast(ability, target)   => {
+                write!(f, "Cast {} at {}", Uuid::from_bytes(*ability), Uuid::from_bytes(*target))
+            },
+            Intent::Approach(resource)      => {
+                write!(f, "Approach resource {}", Uuid::from_bytes(*resource))
+            },
+            Intent::Retreat(threat)         => {
+                write!(f, "Retreat from {}", Uuid::from_bytes(*threat))
+            },
+            Intent::Wait                    => {
+                write!(f, "Wait")
+            },
+        }
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *      Decision      *
+\*  *  *  *  *  *  *  */
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (score {:.3})", self.intent, self.score)?;
+        for factor in self.factors.iter() {
+            write!(f, "\n    {}: {:.3} -> {:.3}", factor.consideration, factor.input, factor.output)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ability::aspect::*,
+        actor::{
+            faction::Relations,
+            inventory::Essence,
+        },
+        context::Context,
+        coords::tests::pos,
+        element::Element,
+    };
+
+    #[test]
+    fn curves() {
+        assert_eq!(Curve::Linear(-1.0, 1.0).respond(0.25), 0.75);
+        assert_eq!(Curve::Linear(2.0, 0.0).respond(0.75), 1.0);
+        assert_eq!(Curve::Power(2.0).respond(0.5), 0.25);
+        assert_eq!(Curve::Logistic(10.0, 0.5).respond(0.5), 0.5);
+        assert_eq!(Curve::Step(0.5).respond(0.4), 0.0);
+        assert_eq!(Curve::Constant(3.0).respond(0.0), 1.0);
+    }
+
+    #[test]
+    fn casting_and_retreat() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let mut relations = Relations::new();
+        relations.set("Crown", "Bandits", Relationship::Hostile);
+        world.set_relations(relations);
+
+        let fireball = Ability::new("Fireball", 20, Aspects::new(Aesthetics::Impressive, Element::Fire, Method::Staff, Morality::Neutral, School::Destruction))
+            .with_range(3)
+            .with_fatigue_cost(5);
+        let fireball_uid = *fireball.uid();
+
+        let mut knight = Actor::new("Knight", pos(0, 0, 0, &ctx), 0, vec![fireball]);
+        knight.set_faction(Some("Crown"));
+        let knight_uid = *knight.uid();
+        let mut squire = Actor::new("Squire", pos(0, 1, -1, &ctx), 0, Vec::new());
+        squire.set_faction(Some("Crown"));
+        let mut bandit = Actor::new("Bandit", pos(2, 0, -2, &ctx), 0, Vec::new());
+        bandit.set_faction(Some("Bandits"));
+        let bandit_uid = *bandit.uid();
+        world.add_actor(knight);
+        world.add_actor(squire);
+        world.add_actor(bandit);
+
+        // A fresh knight attacks the bandit rather than anyone else
+        let profile = Profile::default();
+        let decision = decide(&world, &knight_uid, &profile).unwrap();
+        assert_eq!(decision.intent, Intent::Cast(fireball_uid, bandit_uid));
+        assert_eq!(decision.action, Some(Action::Cast(fireball_uid, Target::Actor(bandit_uid))));
+        assert!(decision.to_string().contains("Benefit: 1.000 -> 1.000"));
+
+        // An exhausted knight backs away instead
+        world.actor_mut(&knight_uid).unwrap().exert(225).unwrap();
+        let decision = decide(&world, &knight_uid, &profile).unwrap();
+        assert_eq!(decision.intent, Intent::Retreat(bandit_uid));
+        let dir = match decision.action {
+            Some(Action::Move(dir)) => dir,
+            other                   => panic!("Expected a move, got {:?}", other),
+        };
+        let mut dest = pos(0, 0, 0, &ctx);
+        dest.translate(&coords::Translation::from(dir), &ctx).unwrap();
+        assert_eq!(dest.distance_to(&pos(2, 0, -2, &ctx)), 3);
+    }
+
+    #[test]
+    fn reagents_required() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        let meteor = Ability::new("Meteor", 30, Aspects::new(Aesthetics::Impressive, Element::Fire, Method::Staff, Morality::Neutral, School::Destruction))
+            .with_range(3)
+            .with_reagent(Essence::new(Element::Fire, 3));
+        let meteor_uid = *meteor.uid();
+        let mage = Actor::new("Mage", pos(0, 0, 0, &ctx), 0, vec![meteor]);
+        let mage_uid = *mage.uid();
+        let target = Actor::new("Target", pos(2, 0, -2, &ctx), 0, Vec::new());
+        let target_uid = *target.uid();
+        world.add_actor(mage);
+        world.add_actor(target);
+
+        // Abilities are not considered castable without the reagents they require
+        let can_cast_meteor = |world: &World| {
+            let mage = world.actor(&mage_uid).unwrap();
+            can_cast(world, mage, mage.ability(&meteor_uid).unwrap(), world.actor(&target_uid).unwrap())
+        };
+        assert!(!can_cast_meteor(&world));
+        world.actor_mut(&mage_uid).unwrap().inventory_mut().store(Essence::new(Element::Fire, 3));
+        assert!(can_cast_meteor(&world));
+    }
+
+    #[test]
+    fn approach_resource() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        let spark = Ability::new("Spark", 5, Aspects::new(Aesthetics::Beautiful, Element::Fire, Method::Wand, Morality::Neutral, School::Destruction));
+        let hermit = Actor::new("Hermit", pos(0, 0, 0, &ctx), 0, vec![spark]);
+        let hermit_uid = *hermit.uid();
+        world.add_actor(hermit);
+
+        let brazier = Resource::new(Element::Fire, State::Full, pos(4, 0, -4, &ctx), 1);
+        let brazier_uid = *brazier.uid();
+        world.add_resource(brazier);
+
+        let decisions = evaluate(&world, &hermit_uid, &Profile::default());
+        assert_eq!(decisions[0].intent, Intent::Approach(brazier_uid));
+        assert_eq!(decisions[0].action, Some(Action::Move(hex_directions::Side::from(coords::Translation::new(1, 0, -1, &ctx).unwrap()))));
+
+        // Harming oneself is never worthwhile
+        assert_eq!(decisions.last().unwrap().score, 0.0);
+        assert!(matches!(decisions.last().unwrap().intent, Intent::Cast(_, target) if target == hermit_uid));
+    }
+}
@@ -41,6 +41,7 @@ Purpose:
 
 use std::{
     error::Error,
+    f64::consts::PI,
     fmt,
     ops::Neg,
 };
@@ -55,6 +56,14 @@ use rand::Rng;
 use serde::{Serialize, Deserialize};
 
 
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Offset applied to line endpoints to break ties when a line passes exactly between two cells
+const LINE_NUDGE: f64 = 1e-6;
+
+
 ///////////////////////////////////////////////////////////////////////////////
 //  Data structures
 ///////////////////////////////////////////////////////////////////////////////
@@ -199,6 +208,53 @@ impl Position {
         translation.magnitude() == 1
     }
 
+    /// Determines the distance (in cells) between this position and the given position
+    pub fn distance_to(&self, other: &Self) -> u32 {
+        self.delta_to(other).magnitude()
+    }
+
+    /// Determines all valid cells within the given radius of this position, including this position
+    pub fn within_radius(&self, radius: usize, ctx: &Context) -> Vec<Self> {
+        let r = radius as i32;
+        let mut cells = Vec::new();
+
+        for dx in -r ..= r {
+            for dy in i32::max(-r, -dx - r) ..= i32::min(r, -dx + r) {
+                let cell = Self {x: self.x + dx, y: self.y + dy, z: self.z - dx - dy};
+                if cell.is_sane(ctx).is_ok() {
+                    cells.push(cell);
+                }
+            }
+        }
+
+        cells
+    }
+
+    /// Determines all valid cells at exactly the given distance from this position
+    pub fn ring(&self, radius: usize, ctx: &Context) -> Vec<Self> {
+        self.within_radius(radius, ctx).into_iter()
+            .filter(|cell| self.distance_to(cell) as usize == radius)
+            .collect()
+    }
+
+    /// Determines the cells along a straight line from this position to the given position, inclusive
+    /// of both endpoints.
+    pub fn line_to(&self, other: &Self) -> Vec<Self> {
+        let dist = self.distance_to(other);
+        if dist == 0 {
+            return vec![*self];
+        }
+
+        // Nudge the endpoints slightly so that lines lying exactly on cell edges round consistently
+        let (ax, ay, az) = (self.x as f64 + LINE_NUDGE, self.y as f64 + LINE_NUDGE, self.z as f64 - 2.0*LINE_NUDGE);
+        let (bx, by, bz) = (other.x as f64 + LINE_NUDGE, other.y as f64 + LINE_NUDGE, other.z as f64 - 2.0*LINE_NUDGE);
+
+        (0 ..= dist).map(|i| {
+            let t = i as f64 / dist as f64;
+            Self::round(ax + (bx - ax)*t, ay + (by - ay)*t, az + (bz - az)*t)
+        }).collect()
+    }
+
 
     /*  *  *  *  *  *  *  *\
      *  Helper Methods    *
@@ -221,6 +277,29 @@ impl Position {
         Ok(())
     }
 
+    /// Rounds fractional cube coordinates to the nearest valid position.
+    fn round(x: f64, y: f64, z: f64) -> Self {
+        let mut rx = x.round();
+        let mut ry = y.round();
+        let mut rz = z.round();
+
+        // Reset the component with the largest rounding error to preserve the x + y + z == 0 requirement
+        let x_diff = (rx - x).abs();
+        let y_diff = (ry - y).abs();
+        let z_diff = (rz - z).abs();
+        if x_diff > y_diff && x_diff > z_diff {
+            rx = -ry - rz;
+        }
+        else if y_diff > z_diff {
+            ry = -rx - rz;
+        }
+        else {
+            rz = -rx - ry;
+        }
+
+        Self {x: rx as i32, y: ry as i32, z: rz as i32}
+    }
+
     /// Blindly translates the position without sanity checking.
     fn blind_translate(&mut self, trans: &Translation) {
         self.x += trans.x();
@@ -276,6 +355,15 @@ impl Translation {
      *  Utility Methods   *
     \*  *  *  *  *  *  *  */
 
+    /// Calculates the bearing of the translation in radians, counter-clockwise from East in the range [0, 2*PI).
+    pub fn bearing(&self) -> f32 {
+        // Project the cube coordinates onto a cartesian plane with X pointing East and Y pointing North
+        let cart_x = self.x as f64 * (PI/6.0).cos();
+        let cart_y = self.x as f64 * (PI/6.0).sin() + self.y as f64;
+
+        cart_y.atan2(cart_x).rem_euclid(2.0*PI) as f32
+    }
+
     /// Calculates and returns the magnitude of the translation i.e., the minimum number of hops required to accomplish it.
     pub fn magnitude(&self) -> u32 {
         let x_abs_mag = i32::abs(self.x) as u32;
@@ -402,3 +490,101 @@ impl fmt::Display for CoordsError {
         }
     }
 }
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Constructs a position for use in tests, panicking if it is invalid in the given context
+    pub(crate) fn pos(x: i32, y: i32, z: i32, ctx: &Context) -> Position {
+        Position::new(x, y, z, ctx).unwrap()
+    }
+
+    #[test]
+    fn distances() {
+        let ctx = Context::default();
+        let origin = pos(0, 0, 0, &ctx);
+
+        assert_eq!(origin.distance_to(&origin), 0);
+        assert_eq!(origin.distance_to(&pos(3, -1, -2, &ctx)), 3);
+        assert_eq!(pos(3, -1, -2, &ctx).distance_to(&origin), 3);
+        assert_eq!(pos(-2, 4, -2, &ctx).distance_to(&pos(2, 0, -2, &ctx)), 4);
+    }
+
+    #[test]
+    fn areas() {
+        let ctx = Context::default();
+        let origin = pos(0, 0, 0, &ctx);
+        let radius = ctx.grid_radius() as i32;
+
+        assert_eq!(origin.within_radius(0, &ctx), vec![origin]);
+        assert_eq!(origin.within_radius(1, &ctx).len(), 7);
+        assert_eq!(origin.within_radius(2, &ctx).len(), 19);
+        assert!(origin.within_radius(2, &ctx).iter().all(|cell| origin.distance_to(cell) <= 2));
+
+        assert_eq!(origin.ring(0, &ctx), vec![origin]);
+        assert_eq!(origin.ring(1, &ctx).len(), 6);
+        assert_eq!(origin.ring(2, &ctx).len(), 12);
+        assert!(origin.ring(2, &ctx).iter().all(|cell| origin.distance_to(cell) == 2));
+
+        // Cells beyond the edge of the grid are omitted
+        let corner = pos(radius, 0, -radius, &ctx);
+        assert_eq!(corner.within_radius(1, &ctx).len(), 4);
+        assert_eq!(corner.ring(1, &ctx).len(), 3);
+        let edge = pos(radius, -1, 1 - radius, &ctx);
+        assert_eq!(edge.ring(1, &ctx).len(), 4);
+    }
+
+    #[test]
+    fn lines() {
+        let ctx = Context::default();
+        let origin = pos(0, 0, 0, &ctx);
+
+        assert_eq!(origin.line_to(&origin), vec![origin]);
+        assert_eq!(origin.line_to(&pos(3, 0, -3, &ctx)),
+                   vec![origin, pos(1, 0, -1, &ctx), pos(2, 0, -2, &ctx), pos(3, 0, -3, &ctx)]);
+
+        // Lines passing exactly between two cells are nudged the same way in either direction
+        let diagonal = pos(2, -1, -1, &ctx);
+        assert_eq!(origin.line_to(&diagonal), vec![origin, pos(1, 0, -1, &ctx), diagonal]);
+        assert_eq!(diagonal.line_to(&origin), vec![diagonal, pos(1, 0, -1, &ctx), origin]);
+
+        // Every step of a line is to a neighbouring cell
+        let line = pos(-3, 1, 2, &ctx).line_to(&pos(2, 2, -4, &ctx));
+        assert_eq!(line.len(), 7);
+        assert!(line.windows(2).all(|pair| pair[0].is_neighbor(&pair[1])));
+    }
+
+    #[test]
+    fn rounding() {
+        assert_eq!(Position::round(0.0, 0.0, 0.0), Position {x: 0, y: 0, z: 0});
+        assert_eq!(Position::round(1.2, -0.9, -0.3), Position {x: 1, y: -1, z: 0});
+
+        // The component with the largest rounding error is recomputed from the others
+        assert_eq!(Position::round(1.4, 0.1, -1.5), Position {x: 1, y: 0, z: -1});
+        assert_eq!(Position::round(0.6, 0.6, -1.2), Position {x: 1, y: 0, z: -1});
+        assert_eq!(Position::round(-0.5, 1.3, -0.8), Position {x: 0, y: 1, z: -1});
+    }
+
+    #[test]
+    fn bearings() {
+        let bearings = [
+            (hex_directions::Side::NorthEast,   PI / 6.0),
+            (hex_directions::Side::North,       PI / 2.0),
+            (hex_directions::Side::NorthWest,   5.0 * PI / 6.0),
+            (hex_directions::Side::SouthWest,   7.0 * PI / 6.0),
+            (hex_directions::Side::South,       3.0 * PI / 2.0),
+            (hex_directions::Side::SouthEast,   11.0 * PI / 6.0),
+        ];
+
+        for (side, bearing) in bearings.iter() {
+            let actual = Translation::from(*side).bearing();
+            assert!((actual as f64 - bearing).abs() < 1e-6, "{:?}: {} != {}", side, actual, bearing);
+        }
+    }
+}
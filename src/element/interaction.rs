@@ -0,0 +1,359 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : element/interaction.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the interactions between elements.
+
+    For every attacker/defender pair of elements, the interaction matrix holds
+    a potency multiplier and a reaction describing what happens to the
+    defender. Pairs absent from the matrix are neutral.
+
+    Custom matrices may be loaded from JSON of the form:
+        [
+            {"attacker": "Fire", "defender": "Ice", "multiplier": 2.0, "reaction": "Melt"},
+            ...
+        ]
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::Path,
+};
+
+use crate::element::Element;
+
+use serde::{Serialize, Deserialize};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Multiplier applied to pairs absent from the matrix
+const NEUTRAL_MULTIPLIER: f64 = 1.0;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of reactions that may occur when one element meets another.
+/// Reactions describe what happens to the defending element.
+#[derive(
+    Debug,
+    Copy, Clone,
+    Eq, PartialEq,
+    Serialize, Deserialize
+)]
+pub enum Reaction {
+    None,
+    Melt,           // Defender thaws and is destroyed
+    Evaporate,      // Defender boils away
+    Extinguish,     // Defender is put out
+    Freeze,         // Defender is frozen solid
+    Ignite,         // Defender flares up
+    Conduct,        // Defender carries the attack further
+    Ground,         // Defender absorbs the attack harmlessly
+    Scatter,        // Defender is pushed away
+    Illuminate,     // Defender is dispelled by light
+    Obscure,        // Defender is swallowed by darkness
+}
+
+/// The result of an attacker element meeting a defender element
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Interaction {
+    pub multiplier: f64,
+    pub reaction:   Reaction,
+}
+
+/// Lookup table of interactions between every attacker/defender pair of elements
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    entries: HashMap<(Element, Element), Interaction>,
+}
+
+/// Serialized form of a single matrix entry
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    attacker:   Element,
+    defender:   Element,
+    multiplier: f64,
+    reaction:   Reaction,
+}
+
+#[derive(Debug)]
+pub enum InteractionError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    DuplicateEntry(Element, Element),
+    InvalidMultiplier(Element, Element, f64),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Interaction {
+    /// Fully-qualified constructor
+    pub fn new(multiplier: f64, reaction: Reaction) -> Self {
+        Self {multiplier, reaction}
+    }
+}
+
+impl Matrix {
+    /// Constructs an empty matrix, in which every pair is neutral
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Constructs a matrix from the given JSON
+    pub fn from_json(json: &str) -> Result<Self, InteractionError> {
+        let entries: Vec<Entry> = serde_json::from_str(json)?;
+
+        let mut matrix = Self::new();
+        for entry in entries {
+            if !entry.multiplier.is_finite() || entry.multiplier < 0.0 {
+                return Err(InteractionError::InvalidMultiplier(entry.attacker, entry.defender, entry.multiplier));
+            }
+            if matrix.entries.contains_key(&(entry.attacker, entry.defender)) {
+                return Err(InteractionError::DuplicateEntry(entry.attacker, entry.defender));
+            }
+
+            matrix.set(entry.attacker, entry.defender, Interaction::new(entry.multiplier, entry.reaction));
+        }
+
+        Ok(matrix)
+    }
+
+    /// Constructs a matrix from the JSON file at the given path
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, InteractionError> {
+        let json = fs::read_to_string(path)?;
+
+        Self::from_json(&json)
+    }
+
+    /// Serializes the matrix to JSON, in the same form accepted by from_json()
+    pub fn to_json(&self) -> String {
+        let mut entries: Vec<Entry> = self.entries.iter()
+            .map(|(&(attacker, defender), interaction)| Entry {
+                attacker,
+                defender,
+                multiplier: interaction.multiplier,
+                reaction:   interaction.reaction,
+            })
+            .collect();
+        entries.sort_by_key(|entry| (entry.attacker, entry.defender));
+
+        serde_json::to_string_pretty(&entries).expect("Failed to serialize interaction matrix")
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the interaction between the given attacker and defender
+    pub fn get(&self, attacker: Element, defender: Element) -> Interaction {
+        match self.entries.get(&(attacker, defender)) {
+            Some(interaction)   => *interaction,
+            None                => Interaction::default(),
+        }
+    }
+
+    pub fn multiplier(&self, attacker: Element, defender: Element) -> f64 {
+        self.get(attacker, defender).multiplier
+    }
+
+    pub fn reaction(&self, attacker: Element, defender: Element) -> Reaction {
+        self.get(attacker, defender).reaction
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the interaction between the given attacker and defender, replacing any existing entry
+    pub fn set(&mut self, attacker: Element, defender: Element, interaction: Interaction) {
+        self.entries.insert((attacker, defender), interaction);
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+/*  *  *  *  *  *  *  *\
+ *    Interaction     *
+\*  *  *  *  *  *  *  */
+impl Default for Interaction {
+    fn default() -> Self {
+        Self {
+            multiplier: NEUTRAL_MULTIPLIER,
+            reaction:   Reaction::None,
+        }
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *       Matrix       *
+\*  *  *  *  *  *  *  */
+impl Default for Matrix {
+    /// Constructs the default CastIron interaction table
+    fn default() -> Self {
+        let table = [
+            // Fire
+            (Element::Fire,     Element::Fire,      0.50,   Reaction::None),
+            (Element::Fire,     Element::Ice,       2.00,   Reaction::Melt),
+            (Element::Fire,     Element::Water,     0.50,   Reaction::Evaporate),
+            (Element::Fire,     Element::Earth,     0.75,   Reaction::None),
+            // Ice
+            (Element::Ice,      Element::Ice,       0.50,   Reaction::None),
+            (Element::Ice,      Element::Fire,      0.50,   Reaction::None),
+            (Element::Ice,      Element::Water,     1.50,   Reaction::Freeze),
+            (Element::Ice,      Element::Wind,      1.25,   Reaction::None),
+            // Wind
+            (Element::Wind,     Element::Unset,     1.00,   Reaction::Scatter),
+            (Element::Wind,     Element::Fire,      1.50,   Reaction::Ignite),
+            (Element::Wind,     Element::Ice,       1.00,   Reaction::Scatter),
+            (Element::Wind,     Element::Water,     1.00,   Reaction::Scatter),
+            (Element::Wind,     Element::Earth,     0.50,   Reaction::None),
+            // Water
+            (Element::Water,    Element::Water,     0.50,   Reaction::None),
+            (Element::Water,    Element::Fire,      2.00,   Reaction::Extinguish),
+            (Element::Water,    Element::Electric,  1.50,   Reaction::Conduct),
+            (Element::Water,    Element::Earth,     1.25,   Reaction::None),
+            // Electric
+            (Element::Electric, Element::Electric,  0.50,   Reaction::None),
+            (Element::Electric, Element::Water,     2.00,   Reaction::Conduct),
+            (Element::Electric, Element::Earth,     0.00,   Reaction::Ground),
+            // Earth
+            (Element::Earth,    Element::Electric,  1.50,   Reaction::None),
+            (Element::Earth,    Element::Wind,      0.50,   Reaction::None),
+            (Element::Earth,    Element::Fire,      1.25,   Reaction::Extinguish),
+            // Light
+            (Element::Light,    Element::Light,     0.50,   Reaction::None),
+            (Element::Light,    Element::Dark,      2.00,   Reaction::Illuminate),
+            // Dark
+            (Element::Dark,     Element::Dark,      0.50,   Reaction::None),
+            (Element::Dark,     Element::Light,     2.00,   Reaction::Obscure),
+        ];
+
+        let mut matrix = Self::new();
+        for &(attacker, defender, multiplier, reaction) in table.iter() {
+            matrix.set(attacker, defender, Interaction::new(multiplier, reaction));
+        }
+
+        matrix
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *  InteractionError  *
+\*  *  *  *  *  *  *  */
+impl From<io::Error> for InteractionError {
+    fn from(src: io::Error) -> Self {
+        InteractionError::Io(src)
+    }
+}
+impl From<serde_json::Error> for InteractionError {
+    fn from(src: serde_json::Error) -> Self {
+        InteractionError::Parse(src)
+    }
+}
+impl Error for InteractionError {}
+impl fmt::Display for InteractionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InteractionError::Io(err)                   => {
+                write!(f, "Failed to read interaction matrix: {}", err)
+            },
+            InteractionError::Parse(err)                => {
+                write!(f, "Failed to parse interaction matrix: {}", err)
+            },
+            InteractionError::DuplicateEntry(atk, def)  => {
+                write!(f, "Duplicate entry for {:?} vs. {:?}", atk, def)
+            },
+            InteractionError::InvalidMultiplier(atk, def, mult) => {
+                write!(f, "Invalid multiplier {} for {:?} vs. {:?}", mult, atk, def)
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_table() {
+        let matrix = Matrix::default();
+
+        assert_eq!(matrix.get(Element::Fire, Element::Ice), Interaction::new(2.0, Reaction::Melt));
+        assert_eq!(matrix.reaction(Element::Water, Element::Electric), Reaction::Conduct);
+
+        // Unlisted pairs are neutral
+        assert_eq!(matrix.get(Element::Light, Element::Fire), Interaction::default());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let matrix = Matrix::default();
+        let reloaded = Matrix::from_json(&matrix.to_json()).unwrap();
+        assert_eq!(matrix, reloaded);
+
+        let custom = Matrix::from_json(r#"[
+            {"attacker": "Fire", "defender": "Ice", "multiplier": 3.0, "reaction": "Melt"}
+        ]"#).unwrap();
+        assert_eq!(custom.multiplier(Element::Fire, Element::Ice), 3.0);
+        assert_eq!(custom.reaction(Element::Water, Element::Electric), Reaction::None);
+
+        let duplicate = Matrix::from_json(r#"[
+            {"attacker": "Fire", "defender": "Ice", "multiplier": 3.0, "reaction": "Melt"},
+            {"attacker": "Fire", "defender": "Ice", "multiplier": 1.0, "reaction": "None"}
+        ]"#);
+        match duplicate {
+            Err(InteractionError::DuplicateEntry(Element::Fire, Element::Ice)) => {},
+            other => panic!("Expected duplicate entry error, got {:?}", other),
+        }
+
+        let negative = Matrix::from_json(r#"[
+            {"attacker": "Fire", "defender": "Ice", "multiplier": -1.0, "reaction": "Melt"}
+        ]"#);
+        assert!(negative.is_err());
+    }
+}
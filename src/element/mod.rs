@@ -1,5 +1,5 @@
 /* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
-Filename : element.rs
+Filename : element/mod.rs
 
 Copyright (C) 2020 CJ McAllister
     This program is free software; you can redistribute it and/or modify
@@ -30,6 +30,13 @@ use rand::{
 use serde::{Serialize, Deserialize};
 
 
+///////////////////////////////////////////////////////////////////////////////
+//  Module Declarations
+///////////////////////////////////////////////////////////////////////////////
+
+pub mod interaction;
+
+
 //////////////////////////////////////////////////////////////////////////////
 //  Data Structures
 //////////////////////////////////////////////////////////////////////////////
@@ -37,11 +44,15 @@ use serde::{Serialize, Deserialize};
 // Enumeration of all element types
 #[derive(
     Debug,
+    Default,
     Copy, Clone,
     Eq, PartialEq,
+    Ord, PartialOrd,
+    Hash,
     Serialize, Deserialize
 )]
 pub enum Element {
+    #[default]
     Unset       = 0,
     Fire        = 1,
     Ice         = 2,
@@ -70,11 +81,6 @@ pub trait Elemental {
 //  Trait Implementations
 ///////////////////////////////////////////////////////////////////////////////
 
-impl Default for Element {
-    fn default() -> Self {
-        Self::Unset
-    }
-}
 impl From<Element> for String {
     fn from(src: Element) -> Self {
         match src {
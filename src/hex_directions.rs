@@ -55,8 +55,9 @@ pub trait HexDirection:
 //  Data structures
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
 pub enum Side {
+    #[default]
     NorthEast,
     North,
     NorthWest,
@@ -65,8 +66,9 @@ pub enum Side {
     SouthEast,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
 pub enum Vertex {
+    #[default]
     East,
     NorthEast,
     NorthWest,
@@ -243,11 +245,6 @@ impl Distribution<Side> for Standard {
         Side::from(rand_num)
     }
 }
-impl Default for Side {
-    fn default() -> Self {
-        Self::NorthEast
-    }
-}
 
 
 /*  *  *  *  *  *  *  *\
@@ -315,11 +312,6 @@ impl Distribution<Vertex> for Standard {
         Vertex::from(rand_num)
     }
 }
-impl Default for Vertex {
-    fn default() -> Self {
-        Self::East
-    }
-}
 
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -65,6 +65,7 @@ macro_rules! debug_println {
 
 #[macro_use]
 pub mod ability;
+pub mod ai;
 pub mod actor;
 pub mod context;
 pub mod coords;
@@ -72,6 +73,8 @@ pub mod element;
 pub mod hex_directions;
 pub mod mechanics;
 pub mod polyfunc;
+pub mod turn;
+pub mod world;
 
 use crate::context::Context;
 
@@ -0,0 +1,395 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : mechanics/control.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines control of resources by factions and actors.
+
+    Every undefeated actor within a resource's radius stakes a claim to it on
+    behalf of its faction, or on its own behalf if it belongs to none. A lone
+    claimant gains control once it has held the resource for long enough,
+    while a resource claimed by several at once is contested: claims stand
+    still and the resource does not regrow until the contest is settled.
+
+    The world records an event whenever control of a resource changes hands.
+    Scoreboards tally points for the resources each controller holds, either
+    by the default rule or by a rule supplied by the game.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    collections::HashMap,
+    fmt,
+    time::Duration,
+};
+
+use crate::{
+    actor::Actor,
+    mechanics::resource::Resource,
+    world::World,
+    Plottable,
+};
+
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default span of game time a lone claimant must hold a resource to gain control (in seconds)
+const DEFAULT_HOLD_SECS: f64 = 12.0;
+
+/// Points awarded by default per second of game time a resource is held uncontested
+const DEFAULT_POINTS_PER_SEC: f64 = 1.0;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the parties which may control a resource
+#[derive(
+    Debug,
+    Clone,
+    Eq, PartialEq,
+    Hash,
+    Serialize, Deserialize
+)]
+pub enum Controller {
+    Faction(String),
+    Actor([u8; 16]),
+}
+
+/// Control state of a single resource
+#[derive(Debug, Clone, PartialEq)]
+pub struct Control {
+    controller: Option<Controller>,
+    claimant:   Option<Controller>,     // Lone party working towards control, if any
+    held:       f64,                    // Game time the claimant has held the resource (in seconds)
+    contested:  bool,
+    hold_secs:  f64,                    // Game time required to gain control (in seconds)
+}
+
+/// Record of control of a resource changing hands
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlEvent {
+    pub resource:   [u8; 16],
+    pub previous:   Option<Controller>,
+    pub controller: Controller,
+    pub tick:       f64,
+}
+
+/// Running tally of the points awarded to each controller
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scoreboard {
+    points: HashMap<Controller, f64>,
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Controller {
+    /// Determines the party on whose behalf the given actor claims resources
+    pub fn of(actor: &Actor) -> Self {
+        match actor.faction() {
+            Some(faction)   => Controller::Faction(faction.to_string()),
+            None            => Controller::Actor(*actor.uid()),
+        }
+    }
+}
+
+impl Control {
+    /// Constructs an uncontrolled state requiring the given span of game time to gain control
+    pub fn new(hold_secs: f64) -> Self {
+        Self {
+            controller: None,
+            claimant:   None,
+            held:       0.0,
+            contested:  false,
+            hold_secs,
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn controller(&self) -> Option<&Controller> {
+        self.controller.as_ref()
+    }
+
+    pub fn claimant(&self) -> Option<&Controller> {
+        self.claimant.as_ref()
+    }
+
+    /// Returns the game time the current claimant has held the resource (in seconds)
+    pub fn held(&self) -> f64 {
+        self.held
+    }
+
+    pub fn is_contested(&self) -> bool {
+        self.contested
+    }
+
+    pub fn hold_secs(&self) -> f64 {
+        self.hold_secs
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn set_controller(&mut self, controller: Option<Controller>) {
+        self.controller = controller;
+        self.claimant = None;
+        self.held = 0.0;
+    }
+
+    pub fn set_hold_secs(&mut self, hold_secs: f64) {
+        self.hold_secs = hold_secs;
+    }
+
+    /// Updates control given the parties currently claiming the resource over the given span
+    /// of game time. Returns true if control changed hands.
+    pub fn update(&mut self, claimants: &[Controller], elapsed_secs: f64) -> bool {
+        self.contested = claimants.len() > 1;
+
+        let claimant = match claimants {
+            [claimant]  => claimant,
+            []          => {
+                // Abandoned claims are forfeit
+                self.claimant = None;
+                self.held = 0.0;
+                return false;
+            },
+            _           => return false,
+        };
+
+        if self.controller.as_ref() == Some(claimant) {
+            self.claimant = None;
+            self.held = 0.0;
+            return false;
+        }
+
+        if self.claimant.as_ref() != Some(claimant) {
+            self.claimant = Some(claimant.clone());
+            self.held = 0.0;
+        }
+
+        self.held += elapsed_secs;
+        if self.held < self.hold_secs {
+            return false;
+        }
+
+        self.controller = self.claimant.take();
+        self.held = 0.0;
+        true
+    }
+}
+
+impl Scoreboard {
+    /// Constructs an empty scoreboard
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the points awarded to the given controller
+    pub fn points(&self, controller: &Controller) -> f64 {
+        self.points.get(controller).cloned().unwrap_or(0.0)
+    }
+
+    /// Returns every controller awarded points, highest first
+    pub fn standings(&self) -> Vec<(Controller, f64)> {
+        let mut standings: Vec<(Controller, f64)> = self.points.iter()
+            .map(|(controller, points)| (controller.clone(), *points))
+            .collect();
+        standings.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        standings
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn award(&mut self, controller: &Controller, points: f64) {
+        *self.points.entry(controller.clone()).or_insert(0.0) += points;
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Awards points for every resource held uncontested over the given span of game time,
+    /// at a fixed rate per second
+    pub fn tally(&mut self, world: &World, elapsed: Duration) {
+        self.tally_with(world, elapsed, |resource, _controller, secs| {
+            if resource.is_contested() { 0.0 } else { DEFAULT_POINTS_PER_SEC * secs }
+        });
+    }
+
+    /// Awards points for every controlled resource over the given span of game time, as
+    /// determined by the given rule
+    pub fn tally_with<F>(&mut self, world: &World, elapsed: Duration, rule: F)
+    where F: Fn(&Resource, &Controller, f64) -> f64 {
+        for resource in world.resources() {
+            if let Some(controller) = resource.controller() {
+                let points = rule(resource, controller, elapsed.as_secs_f64());
+                self.award(controller, points);
+            }
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Determines the distinct parties claiming the given resource, in order of the world's actors
+pub fn claimants(world: &World, resource: &Resource) -> Vec<Controller> {
+    let mut claimants = Vec::new();
+
+    let present = world.actors().iter()
+        .filter(|actor| !actor.is_defeated() && resource.covers(actor.origin()));
+    for actor in present {
+        let claimant = Controller::of(actor);
+        if !claimants.contains(&claimant) {
+            claimants.push(claimant);
+        }
+    }
+
+    claimants
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+/*  *  *  *  *  *  *  *\
+ *      Control       *
+\*  *  *  *  *  *  *  */
+impl Default for Control {
+    fn default() -> Self {
+        Self::new(DEFAULT_HOLD_SECS)
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *     Controller     *
+\*  *  *  *  *  *  *  */
+impl fmt::Display for Controller {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Controller::Faction(faction)    => {
+                write!(f, "Faction {}", faction)
+            },
+            Controller::Actor(uid)          => {
+                write!(f, "Actor {}", Uuid::from_bytes(*uid))
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        context::Context,
+        coords::tests::pos,
+        element::Element,
+        hex_directions::Side,
+        mechanics::resource::State,
+    };
+
+    #[test]
+    fn control_and_contests() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        let shrine = Resource::new(Element::Light, State::Full, pos(0, 0, 0, &ctx), 1).with_hold_secs(6.0);
+        let shrine_uid = *shrine.uid();
+        world.add_resource(shrine);
+
+        let mut knight = Actor::new("Knight", pos(1, 0, -1, &ctx), 0, Vec::new());
+        knight.set_faction(Some("Crown"));
+        let knight_uid = *knight.uid();
+        let mut bandit = Actor::new("Bandit", pos(0, 2, -2, &ctx), 0, Vec::new());
+        bandit.set_faction(Some("Bandits"));
+        let bandit_uid = *bandit.uid();
+        world.add_actor(knight);
+        world.add_actor(bandit);
+
+        // The knight claims the shrine for the crown
+        world.advance(Duration::from_secs(3));
+        assert_eq!(world.resource(&shrine_uid).unwrap().controller(), None);
+        world.advance(Duration::from_secs(3));
+        let crown = Controller::Faction("Crown".to_string());
+        assert_eq!(world.resource(&shrine_uid).unwrap().controller(), Some(&crown));
+        assert_eq!(world.take_control_events(), vec![ControlEvent {
+            resource:   shrine_uid,
+            previous:   None,
+            controller: crown.clone(),
+            tick:       6.0,
+        }]);
+
+        // With the bandit present, the shrine is contested and does not regrow
+        world.actor_mut(&bandit_uid).unwrap().move_one_cell(Side::South, &ctx).unwrap();
+        world.resource_mut(&shrine_uid).unwrap().consume();
+        world.advance(Duration::from_secs(30));
+        let shrine = world.resource(&shrine_uid).unwrap();
+        assert!(shrine.is_contested());
+        assert_eq!(shrine.state(), State::High);
+        assert_eq!(shrine.controller(), Some(&crown));
+
+        let mut scores = Scoreboard::new();
+        scores.tally(&world, Duration::from_secs(10));
+        assert_eq!(scores.points(&crown), 0.0);
+
+        // Once the knight leaves, the bandits take over
+        world.actor_mut(&knight_uid).unwrap().move_one_cell(Side::NorthEast, &ctx).unwrap();
+        world.advance(Duration::from_secs(6));
+        let bandits = Controller::Faction("Bandits".to_string());
+        let shrine = world.resource(&shrine_uid).unwrap();
+        assert!(!shrine.is_contested());
+        assert_eq!(shrine.controller(), Some(&bandits));
+        assert_eq!(world.take_control_events()[0].previous, Some(crown.clone()));
+        assert!(world.take_control_events().is_empty());
+
+        scores.tally(&world, Duration::from_secs(10));
+        scores.tally_with(&world, Duration::from_secs(10), |resource, _controller, _secs| resource.state() as u8 as f64);
+        assert_eq!(scores.standings(), vec![(bandits, 10.0 + State::Full as u8 as f64), (crown, 0.0)]);
+    }
+}
@@ -24,7 +24,11 @@ Purpose:
 //  Module Declarations
 ///////////////////////////////////////////////////////////////////////////////
 
+pub mod control;
 #[macro_use]
+pub mod movement;
+pub mod network;
 pub mod obstacle;
+pub mod reaction;
 pub mod resource;
 pub mod weather;
@@ -0,0 +1,615 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : mechanics/movement.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines terrain-aware movement.
+
+    Entering a cell costs movement points, determined by the terrain of the
+    cell, any obstacle occupying it, and the current weather. Solid obstacles
+    cannot be passed at all. Hastened actors move at half the usual cost,
+    while tired actors pay extra for every cell. Actors unable to act, being
+    collapsed from exhaustion or incapacitated by a status, cannot move.
+
+    Reachable sets are computed with the same per-actor costs, so that a UI
+    may highlight where an actor could move within a budget.
+
+    No two actors may occupy the same cell. Depending on the world's
+    collision rule, moving into an occupied cell is either blocked, swaps the
+    two actors, or pushes the occupant one cell further along.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    error::Error,
+    fmt,
+};
+
+use crate::{
+    actor::{
+        Actor,
+        status::Kind,
+    },
+    coords,
+    element::{
+        Element,
+        Elemental,
+    },
+    hex_directions,
+    mechanics::weather::Intensity,
+    world::World,
+    Plottable,
+};
+
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Movement cost of entering a cell with no special terrain
+const DEFAULT_TERRAIN_COST: u32 = 1;
+
+/// Factor by which Hastened actors divide the cost of each move (rounding up)
+const HASTE_COST_DIVISOR: u32 = 2;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Per-cell movement costs of the terrain of a world
+#[derive(Debug, Clone, PartialEq)]
+pub struct Terrain {
+    default_cost:   u32,
+    costs:          HashMap<coords::Position, u32>,
+}
+
+/// Enumeration of the ways a move into an occupied cell may be resolved
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Collision {
+    #[default]
+    Block,  // The move is prevented
+    Swap,   // The mover and occupant exchange cells
+    Push,   // The occupant is pushed one cell further in the direction of the move
+}
+
+#[derive(Debug)]
+pub enum MovementError {
+    UnknownActor([u8; 16]),
+    Incapacitated([u8; 16]),
+    OutOfBounds(coords::CoordsError),
+    Obstructed(coords::Position, [u8; 16]),     // Destination, and the solid obstacle occupying it
+    Occupied(coords::Position, [u8; 16]),       // Destination, and the actor occupying it
+    PushBlocked(coords::Position),              // Cell into which the occupant could not be pushed
+    InsufficientPoints(u32, u32),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Terrain {
+    /// Constructs terrain of uniform cost
+    pub fn new(default_cost: u32) -> Self {
+        Self {
+            default_cost,
+            costs: HashMap::new(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    /// Returns the cost of entering the given cell, excluding obstacles and weather
+    pub fn cost(&self, pos: &coords::Position) -> u32 {
+        self.costs.get(pos).cloned().unwrap_or(self.default_cost)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn set_cost(&mut self, pos: coords::Position, cost: u32) {
+        self.costs.insert(pos, cost);
+    }
+
+    pub fn clear_cost(&mut self, pos: &coords::Position) {
+        self.costs.remove(pos);
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Returns the additional cost of passing through an obstacle of the given element, or None if impassable
+pub fn obstacle_cost(element: Element) -> Option<u32> {
+    match element {
+        Element::Water  => Some(2),     // Wading
+        Element::Fire   => Some(3),     // Braving the flames
+        Element::Wind   => Some(1),     // Pushing through gusts
+        Element::Dark   => Some(1),     // Feeling the way
+        Element::Light  => Some(0),
+        Element::Unset      |
+        Element::Ice        |
+        Element::Electric   |
+        Element::Earth      => None,
+    }
+}
+
+/// Returns the additional cost of moving through weather of the given element and intensity
+pub fn weather_cost(element: Element, intensity: &Intensity) -> u32 {
+    let severity = match intensity {
+        Intensity::None     => 0,
+        Intensity::Mild     => 0,
+        Intensity::Strong   => 1,
+        Intensity::Severe   => 2,
+        Intensity::Max      => 3,
+    };
+
+    match element {
+        // Downpours and blizzards bog everything down
+        Element::Water  |
+        Element::Ice    => severity,
+        // Gales and sandstorms only hinder once they are severe
+        Element::Wind   |
+        Element::Earth  => severity.saturating_sub(1),
+        _               => 0,
+    }
+}
+
+/// Determines if obstacles of the given element block movement entirely
+pub fn is_solid(element: Element) -> bool {
+    obstacle_cost(element).is_none()
+}
+
+/// Determines the cost of entering the given cell, or None if it cannot be entered.
+/// Actors occupying the cell are not considered.
+pub fn step_cost(world: &World, pos: &coords::Position) -> Option<u32> {
+    entry_cost(world, pos).ok()
+}
+
+/// Determines the cost for the given actor to move a single cell in the given direction,
+/// subject to the world's collision rule and the actor's haste and exhaustion
+pub fn move_cost(world: &World, actor_uid: &[u8; 16], dir: hex_directions::Side) -> Result<u32, MovementError> {
+    let dest = destination(world, actor_uid, dir)?;
+    let cost = entry_cost(world, &dest)?;
+
+    let actor = world.actor(actor_uid).ok_or(MovementError::UnknownActor(*actor_uid))?;
+    if !actor.can_act() {
+        return Err(MovementError::Incapacitated(*actor_uid));
+    }
+    let cost = actor_cost(actor, cost);
+
+    if let Some(occupant) = world.actor_at(&dest) {
+        match world.collision() {
+            Collision::Block    => return Err(MovementError::Occupied(dest, *occupant.uid())),
+            Collision::Swap     => {},
+            Collision::Push     => { push_destination(world, &dest, dir)?; },
+        }
+    }
+
+    Ok(cost)
+}
+
+/// Moves the given actor a single cell in the given direction, if the cost of doing so is within budget.
+/// Any occupant of the destination is swapped or pushed according to the world's collision rule.
+/// Returns the cost spent.
+pub fn step(world:      &mut World,
+            actor_uid:  &[u8; 16],
+            dir:        hex_directions::Side,
+            budget:     u32) -> Result<u32, MovementError> {
+    let cost = move_cost(world, actor_uid, dir)?;
+    if cost > budget {
+        return Err(MovementError::InsufficientPoints(cost, budget));
+    }
+
+    let dest = destination(world, actor_uid, dir)?;
+    let occupant = world.actor_at(&dest).map(|occupant| *occupant.uid());
+    let ctx = world.ctx().clone();
+
+    // Clear the way before moving in
+    if let Some(occupant_uid) = occupant {
+        let displacement = match world.collision() {
+            Collision::Swap => hex_directions::Side::from(-coords::Translation::from(dir)),
+            _               => dir,
+        };
+        let occupant = world.actor_mut(&occupant_uid).ok_or(MovementError::UnknownActor(occupant_uid))?;
+        occupant.move_one_cell(displacement, &ctx)?;
+    }
+
+    let actor = world.actor_mut(actor_uid).ok_or(MovementError::UnknownActor(*actor_uid))?;
+    actor.move_one_cell(dir, &ctx)?;
+
+    Ok(cost)
+}
+
+/// Computes every cell the given actor could reach within the given budget, along with the
+/// cheapest cost of reaching each, priced as by move_cost. The actor's own cell is included at
+/// no cost. Cells occupied by other actors are neither entered nor passed through.
+/// Actors unable to act reach nothing at all.
+pub fn reachable(world: &World, actor_uid: &[u8; 16], budget: u32) -> HashMap<coords::Position, u32> {
+    search(world, actor_uid, budget).into_iter()
+        .map(|(pos, (cost, _previous))| (pos, cost))
+        .collect()
+}
+
+/// Computes every cell the given actor could reach within the given budget as reachable() does,
+/// along with the cell from which each is entered on the cheapest route to it. The actor's own
+/// cell is entered from itself.
+pub fn search(world: &World, actor_uid: &[u8; 16], budget: u32) -> HashMap<coords::Position, (u32, coords::Position)> {
+    let mut reached = HashMap::new();
+    let actor = match world.actor(actor_uid) {
+        Some(actor) if actor.can_act()  => actor,
+        _                               => return reached,
+    };
+
+    let origin = *actor.origin();
+    reached.insert(origin, (0, origin));
+
+    let mut frontier = VecDeque::new();
+    frontier.push_back(origin);
+
+    while let Some(pos) = frontier.pop_front() {
+        let (spent, _previous) = reached[&pos];
+
+        for neighbour in pos.ring(1, world.ctx()) {
+            if world.actor_at(&neighbour).is_some() {
+                continue;
+            }
+            let total = match step_cost(world, &neighbour) {
+                Some(cost)  => spent.saturating_add(actor_cost(actor, cost)),
+                None        => continue,
+            };
+            if total > budget {
+                continue;
+            }
+
+            // Revisit cells whenever a cheaper route to them is found
+            if reached.get(&neighbour).is_none_or(|&(best, _)| total < best) {
+                reached.insert(neighbour, (total, pos));
+                frontier.push_back(neighbour);
+            }
+        }
+    }
+
+    reached
+}
+
+/// Traces the route to the given goal through the results of a search, as the cells to be entered
+/// in turn. The route to the searching actor's own cell is empty.
+/// Returns None if the goal was not reached by the search.
+pub fn route(reached: &HashMap<coords::Position, (u32, coords::Position)>, goal: &coords::Position) -> Option<Vec<coords::Position>> {
+    let mut route = Vec::new();
+    let mut pos = *goal;
+
+    loop {
+        let (_cost, previous) = reached.get(&pos)?;
+        if *previous == pos {
+            break;
+        }
+        route.push(pos);
+        pos = *previous;
+    }
+
+    route.reverse();
+    Some(route)
+}
+
+
+/// Adjusts the cost of entering a cell for the given actor's haste and exhaustion
+fn actor_cost(actor: &Actor, cost: u32) -> u32 {
+    let cost = if actor.has_status(Kind::Hastened) {
+        cost.div_ceil(HASTE_COST_DIVISOR)
+    } else {
+        cost
+    };
+    let penalty = actor.exhaustion().movement_penalty().min(u32::MAX as usize) as u32;
+
+    cost.saturating_add(penalty)
+}
+
+/// Determines the cell the given actor would enter by moving in the given direction
+fn destination(world: &World, actor_uid: &[u8; 16], dir: hex_directions::Side) -> Result<coords::Position, MovementError> {
+    let mut dest = *world.actor(actor_uid).ok_or(MovementError::UnknownActor(*actor_uid))?.origin();
+    dest.translate(&coords::Translation::from(dir), world.ctx())?;
+
+    Ok(dest)
+}
+
+/// Determines the cost of entering the given cell, reporting the obstacle that prevents it if any
+fn entry_cost(world: &World, pos: &coords::Position) -> Result<u32, MovementError> {
+    let mut cost = world.terrain().cost(pos);
+
+    for obstacle in world.obstacles().iter().filter(|obstacle| obstacle.contains(pos)) {
+        cost += obstacle_cost(obstacle.element()).ok_or(MovementError::Obstructed(*pos, *obstacle.uid()))?;
+    }
+
+    let weather = world.weather();
+    cost += weather_cost(weather.element(), &weather.intensity(world.tick()));
+
+    Ok(cost)
+}
+
+/// Determines the cell into which the occupant of the given cell would be pushed
+fn push_destination(world: &World, from: &coords::Position, dir: hex_directions::Side) -> Result<coords::Position, MovementError> {
+    let mut dest = *from;
+    if dest.translate(&coords::Translation::from(dir), world.ctx()).is_err() ||
+       entry_cost(world, &dest).is_err() ||
+       world.actor_at(&dest).is_some() {
+        return Err(MovementError::PushBlocked(dest));
+    }
+
+    Ok(dest)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for Terrain {
+    fn default() -> Self {
+        Self::new(DEFAULT_TERRAIN_COST)
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *   MovementError    *
+\*  *  *  *  *  *  *  */
+impl From<coords::CoordsError> for MovementError {
+    fn from(src: coords::CoordsError) -> Self {
+        MovementError::OutOfBounds(src)
+    }
+}
+impl Error for MovementError {}
+impl fmt::Display for MovementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MovementError::UnknownActor(uid)                    => {
+                write!(f, "Unknown actor {}", Uuid::from_bytes(*uid))
+            },
+            MovementError::Incapacitated(uid)                   => {
+                write!(f, "Actor {} is unable to act", Uuid::from_bytes(*uid))
+            },
+            MovementError::OutOfBounds(err)                     => {
+                write!(f, "Destination out of bounds: {:?}", err)
+            },
+            MovementError::Obstructed(pos, uid)                 => {
+                write!(f, "Cell {} is blocked by obstacle {}", pos, Uuid::from_bytes(*uid))
+            },
+            MovementError::Occupied(pos, uid)                   => {
+                write!(f, "Cell {} is occupied by actor {}", pos, Uuid::from_bytes(*uid))
+            },
+            MovementError::PushBlocked(pos)                     => {
+                write!(f, "Cannot push into cell {}", pos)
+            },
+            MovementError::InsufficientPoints(cost, budget)     => {
+                write!(f, "Move costs {} points, but only {} remain", cost, budget)
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        actor::{
+            Actor,
+            status::StatusEffect,
+        },
+        context::Context,
+        coords::tests::pos,
+        element::interaction::Matrix,
+        mechanics::{
+            obstacle::Obstacle,
+            weather,
+        },
+        polyfunc::PolyFunc,
+    };
+
+    #[test]
+    fn costs() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let actor = Actor::new("Walker", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let uid = *actor.uid();
+        world.add_actor(actor);
+
+        world.add_obstacle(Obstacle::new(vec![pos(1, 0, -1, &ctx)], Element::Earth).unwrap());
+        world.add_obstacle(Obstacle::new(vec![pos(0, 1, -1, &ctx)], Element::Water).unwrap());
+
+        match step(&mut world, &uid, hex_directions::Side::NorthEast, 10) {
+            Err(MovementError::Obstructed(_, _)) => {},
+            other => panic!("Expected obstructed, got {:?}", other),
+        }
+        match step(&mut world, &uid, hex_directions::Side::North, 2) {
+            Err(MovementError::InsufficientPoints(3, 2)) => {},
+            other => panic!("Expected insufficient points, got {:?}", other),
+        }
+        assert_eq!(step(&mut world, &uid, hex_directions::Side::North, 3).unwrap(), 3);
+        assert_eq!(world.actor(&uid).unwrap().origin(), &pos(0, 1, -1, &ctx));
+
+        // Severe rain slows everything down
+        let storm = weather::Event::new(Element::Water, PolyFunc::new(255.0, 100.0, 0.0));
+        world.set_weather(storm);
+        world.advance(std::time::Duration::from_secs(50));
+        assert_eq!(step_cost(&world, &pos(0, 0, 0, &ctx)), Some(3));
+    }
+
+    #[test]
+    fn haste() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let actor = Actor::new("Runner", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let uid = *actor.uid();
+        world.add_actor(actor);
+        world.terrain_mut().set_cost(pos(1, 0, -1, &ctx), 3);
+        assert_eq!(move_cost(&world, &uid, hex_directions::Side::NorthEast).unwrap(), 3);
+
+        let haste = StatusEffect::new(Kind::Hastened, Element::Wind, uid, 0.0, 10.0, 1.0);
+        world.actor_mut(&uid).unwrap().apply_status(haste, &Matrix::default(), 0.0);
+        assert_eq!(move_cost(&world, &uid, hex_directions::Side::NorthEast).unwrap(), 2);
+        assert_eq!(move_cost(&world, &uid, hex_directions::Side::North).unwrap(), 1);
+    }
+
+    #[test]
+    fn exhaustion_and_incapacitation() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        // Tired actors pay extra for every cell
+        let actor = Actor::new("Walker", pos(0, 0, 0, &ctx), 130, Vec::new());
+        let uid = *actor.uid();
+        world.add_actor(actor);
+        assert_eq!(move_cost(&world, &uid, hex_directions::Side::North).unwrap(), 2);
+
+        // Collapsed actors cannot move at all
+        world.actor_mut(&uid).unwrap().exert(125).unwrap();
+        match step(&mut world, &uid, hex_directions::Side::North, u32::MAX) {
+            Err(MovementError::Incapacitated(_)) => {},
+            other => panic!("Expected incapacitated, got {:?}", other),
+        }
+
+        // Nor can frozen ones
+        let frozen = Actor::new("Statue", pos(3, 0, -3, &ctx), 0, Vec::new());
+        let frozen_uid = *frozen.uid();
+        world.add_actor(frozen);
+        let ice = StatusEffect::new(Kind::Frozen, Element::Ice, uid, 0.0, 10.0, 1.0);
+        world.actor_mut(&frozen_uid).unwrap().apply_status(ice, &Matrix::default(), 0.0);
+        match step(&mut world, &frozen_uid, hex_directions::Side::North, u32::MAX) {
+            Err(MovementError::Incapacitated(_)) => {},
+            other => panic!("Expected incapacitated, got {:?}", other),
+        }
+        assert_eq!(world.actor(&frozen_uid).unwrap().origin(), &pos(3, 0, -3, &ctx));
+    }
+
+    #[test]
+    fn reachable_set() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let walker = Actor::new("Walker", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let uid = *walker.uid();
+        world.add_actor(walker);
+
+        let reach = reachable(&world, &uid, 1);
+        assert_eq!(reach.len(), 7);
+
+        // Expensive terrain around the origin is routed around where possible
+        world.terrain_mut().set_cost(pos(1, 0, -1, &ctx), 5);
+        let reach = reachable(&world, &uid, 2);
+        assert_eq!(reach.get(&pos(1, 0, -1, &ctx)), None);
+        assert_eq!(reach.get(&pos(2, -1, -1, &ctx)), Some(&2));
+
+        // Routes follow the cheapest path found by the search
+        let reached = search(&world, &uid, 2);
+        assert_eq!(route(&reached, &pos(2, -1, -1, &ctx)), Some(vec![pos(1, -1, 0, &ctx), pos(2, -1, -1, &ctx)]));
+        assert_eq!(route(&reached, &pos(0, 0, 0, &ctx)), Some(Vec::new()));
+        assert_eq!(route(&reached, &pos(1, 0, -1, &ctx)), None);
+    }
+
+    #[test]
+    fn reachable_by_condition() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+        world.set_terrain(Terrain::new(2));
+
+        let tired = Actor::new("Tired", pos(0, 0, 0, &ctx), 130, Vec::new());
+        let tired_uid = *tired.uid();
+        let hastened = Actor::new("Hastened", pos(8, 0, -8, &ctx), 0, Vec::new());
+        let hastened_uid = *hastened.uid();
+        let fresh = Actor::new("Fresh", pos(0, 8, -8, &ctx), 0, Vec::new());
+        let fresh_uid = *fresh.uid();
+        world.add_actor(tired);
+        world.add_actor(hastened);
+        world.add_actor(fresh);
+        let haste = StatusEffect::new(Kind::Hastened, Element::Wind, hastened_uid, 0.0, 10.0, 1.0);
+        world.actor_mut(&hastened_uid).unwrap().apply_status(haste, &Matrix::default(), 0.0);
+
+        // Reachable costs agree with the cost of each move
+        let reach = reachable(&world, &fresh_uid, 2);
+        assert_eq!(reach.len(), 7);
+        assert_eq!(reach.get(&pos(0, 9, -9, &ctx)), Some(&move_cost(&world, &fresh_uid, hex_directions::Side::North).unwrap()));
+
+        // Tired actors cannot afford even a single cell, while hastened actors go twice as far
+        let reach = reachable(&world, &tired_uid, 2);
+        assert_eq!(move_cost(&world, &tired_uid, hex_directions::Side::North).unwrap(), 3);
+        assert_eq!(reach.len(), 1);
+        assert_eq!(reachable(&world, &tired_uid, 3).get(&pos(0, 1, -1, &ctx)), Some(&3));
+        let reach = reachable(&world, &hastened_uid, 2);
+        assert_eq!(reach.len(), 19);
+        assert_eq!(reach.get(&pos(8, 1, -9, &ctx)), Some(&1));
+
+        // Actors unable to act reach nowhere
+        world.actor_mut(&tired_uid).unwrap().exert(125).unwrap();
+        assert!(reachable(&world, &tired_uid, u32::MAX).is_empty());
+    }
+
+    #[test]
+    fn collisions() {
+        let ctx = Context::default();
+        let mut world = World::new(ctx.clone());
+
+        let mover = Actor::new("Mover", pos(0, 0, 0, &ctx), 0, Vec::new());
+        let mover_uid = *mover.uid();
+        world.add_actor(mover);
+        let occupant = Actor::new("Occupant", pos(0, 1, -1, &ctx), 0, Vec::new());
+        let occupant_uid = *occupant.uid();
+        world.add_actor(occupant);
+
+        match step(&mut world, &mover_uid, hex_directions::Side::North, 10) {
+            Err(MovementError::Occupied(_, uid)) => assert_eq!(uid, occupant_uid),
+            other => panic!("Expected occupied, got {:?}", other),
+        }
+        assert!(!reachable(&world, &mover_uid, 1).contains_key(&pos(0, 1, -1, &ctx)));
+
+        world.set_collision(Collision::Swap);
+        step(&mut world, &mover_uid, hex_directions::Side::North, 10).unwrap();
+        assert_eq!(world.actor(&mover_uid).unwrap().origin(), &pos(0, 1, -1, &ctx));
+        assert_eq!(world.actor(&occupant_uid).unwrap().origin(), &pos(0, 0, 0, &ctx));
+
+        // Pushing requires room behind the occupant
+        world.set_collision(Collision::Push);
+        world.add_obstacle(Obstacle::new(vec![pos(0, -1, 1, &ctx)], Element::Earth).unwrap());
+        match step(&mut world, &mover_uid, hex_directions::Side::South, 10) {
+            Err(MovementError::PushBlocked(_)) => {},
+            other => panic!("Expected push blocked, got {:?}", other),
+        }
+        step(&mut world, &occupant_uid, hex_directions::Side::North, 10).unwrap();
+        assert_eq!(world.actor(&occupant_uid).unwrap().origin(), &pos(0, 1, -1, &ctx));
+        assert_eq!(world.actor(&mover_uid).unwrap().origin(), &pos(0, 2, -2, &ctx));
+    }
+}
@@ -0,0 +1,280 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : mechanics/network.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines networks of resources, joined by ley lines.
+
+    Resources of the same element lying within the link distance of one
+    another are linked, so long as no obstacle stands on the line between
+    them. Linked resources form a network which shares its state: drawing
+    upon any node drains the fullest node of the network, and replenishing
+    any node fills the emptiest, so that the network rises and falls evenly.
+
+    The world maintains the graph of links as resources are added, removed
+    or moved, and as obstacles change.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+
+use crate::{
+    element::{
+        Element,
+        Elemental,
+    },
+    mechanics::resource::Resource,
+    world::World,
+    Plottable,
+};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default maximum distance between linked resources (in cells)
+const DEFAULT_LINK_DISTANCE: usize = 6;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Graph of the ley lines linking the resources of a world
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    link_distance:  usize,                              // Maximum distance between linked resources (in cells)
+    links:          HashMap<[u8; 16], Vec<[u8; 16]>>,   // Resources linked to each node
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Network {
+    /// Constructs an empty network linking resources within the given distance
+    pub fn new(link_distance: usize) -> Self {
+        Self {
+            link_distance,
+            links:          HashMap::new(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn link_distance(&self) -> usize {
+        self.link_distance
+    }
+
+    /// Returns the resources directly linked to the given resource
+    pub fn links(&self, uid: &[u8; 16]) -> &[[u8; 16]] {
+        self.links.get(uid).map_or(&[], |links| links.as_slice())
+    }
+
+    /// Determines if the two given resources are directly linked
+    pub fn is_linked(&self, a: &[u8; 16], b: &[u8; 16]) -> bool {
+        self.links(a).contains(b)
+    }
+
+    /// Returns every resource in the same network as the given resource, beginning with
+    /// the resource itself and proceeding outwards along its links
+    pub fn members(&self, uid: &[u8; 16]) -> Vec<[u8; 16]> {
+        let mut members = vec![*uid];
+        let mut frontier = VecDeque::from(vec![*uid]);
+
+        while let Some(node) = frontier.pop_front() {
+            for link in self.links(&node) {
+                if !members.contains(link) {
+                    members.push(*link);
+                    frontier.push_back(*link);
+                }
+            }
+        }
+
+        members
+    }
+
+    /// Returns every distinct network of linked resources, excluding lone resources
+    pub fn networks(&self) -> Vec<Vec<[u8; 16]>> {
+        let mut nodes: Vec<&[u8; 16]> = self.links.keys().collect();
+        nodes.sort();
+
+        let mut networks: Vec<Vec<[u8; 16]>> = Vec::new();
+        for node in nodes {
+            if self.links(node).is_empty() || networks.iter().any(|network| network.contains(node)) {
+                continue;
+            }
+            networks.push(self.members(node));
+        }
+
+        networks
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the link distance. The network must be rebuilt for it to take effect.
+    pub fn set_link_distance(&mut self, link_distance: usize) {
+        self.link_distance = link_distance;
+    }
+
+    /// Adds the given resource to the network, linked to the given neighbours
+    pub fn insert(&mut self, uid: &[u8; 16], neighbours: &[[u8; 16]]) {
+        self.remove(uid);
+
+        for neighbour in neighbours {
+            self.links.entry(*neighbour).or_default().push(*uid);
+        }
+        self.links.insert(*uid, neighbours.to_vec());
+    }
+
+    /// Removes the given resource and all of its links from the network
+    pub fn remove(&mut self, uid: &[u8; 16]) {
+        if let Some(neighbours) = self.links.remove(uid) {
+            for neighbour in neighbours {
+                if let Some(links) = self.links.get_mut(&neighbour) {
+                    links.retain(|link| link != uid);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.links.clear();
+    }
+
+    /// Rebuilds every link of the network from the resources and obstacles of the given world
+    pub fn rebuild(&mut self, world: &World) {
+        self.clear();
+
+        for resource in world.resources() {
+            let neighbours = neighbours(world, resource, self.link_distance);
+            self.links.insert(*resource.uid(), neighbours);
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Determines if a ley line may run between the two given resources
+pub fn can_link(world: &World, a: &Resource, b: &Resource, link_distance: usize) -> bool {
+    a.uid() != b.uid() &&
+    a.element() != Element::Unset &&
+    a.element() == b.element() &&
+    a.origin().distance_to(b.origin()) as usize <= link_distance &&
+    world.has_line_of_sight(a.origin(), b.origin())
+}
+
+/// Determines the resources of the given world to which the given resource may be linked
+pub fn neighbours(world: &World, resource: &Resource, link_distance: usize) -> Vec<[u8; 16]> {
+    world.resources().iter()
+        .filter(|other| can_link(world, resource, other, link_distance))
+        .map(|other| *other.uid())
+        .collect()
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+/*  *  *  *  *  *  *  *\
+ *      Network       *
+\*  *  *  *  *  *  *  */
+impl Default for Network {
+    fn default() -> Self {
+        Self::new(DEFAULT_LINK_DISTANCE)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        context::Context,
+        coords::tests::pos,
+        mechanics::{
+            obstacle::Obstacle,
+            resource::State,
+        },
+    };
+
+    #[test]
+    fn ley_lines() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+        world.set_link_distance(4);
+
+        let a = Resource::new(Element::Fire, State::Full, pos(0, 0, 0, &ctx), 1);
+        let b = Resource::new(Element::Fire, State::Full, pos(3, 0, -3, &ctx), 1);
+        let c = Resource::new(Element::Fire, State::Full, pos(6, 0, -6, &ctx), 1);
+        let spring = Resource::new(Element::Water, State::Full, pos(0, 2, -2, &ctx), 1);
+        let (a_uid, b_uid, c_uid, spring_uid) = (*a.uid(), *b.uid(), *c.uid(), *spring.uid());
+        world.add_resource(a);
+        world.add_resource(b);
+        world.add_resource(c);
+        world.add_resource(spring);
+
+        // Fire resources chain together, while the spring stands alone
+        assert!(world.network().is_linked(&a_uid, &b_uid));
+        assert!(!world.network().is_linked(&a_uid, &c_uid));
+        assert_eq!(world.network().members(&a_uid), vec![a_uid, b_uid, c_uid]);
+        assert_eq!(world.network().members(&spring_uid), vec![spring_uid]);
+        assert_eq!(world.network().networks().len(), 1);
+
+        // Drawing upon one node drains the network evenly
+        assert_eq!(world.consume_resource(&a_uid), Some((a_uid, State::Full as u8)));
+        assert_eq!(world.consume_resource(&a_uid), Some((b_uid, State::Full as u8)));
+        assert_eq!(world.consume_resource(&a_uid), Some((c_uid, State::Full as u8)));
+        assert!(world.resources()[..3].iter().all(|resource| resource.state() == State::High));
+        world.replenish_resource(&c_uid, 2);
+        assert_eq!(world.resource(&c_uid).unwrap().state(), State::Full);
+        assert_eq!(world.resource(&a_uid).unwrap().state(), State::High);
+        assert_eq!(world.resource(&b_uid).unwrap().state(), State::Full);
+
+        // An obstacle on the ley line cuts the link
+        world.add_obstacle(Obstacle::new(vec![pos(5, 0, -5, &ctx)], Element::Earth).unwrap());
+        assert!(!world.network().is_linked(&b_uid, &c_uid));
+        assert_eq!(world.network().members(&a_uid), vec![a_uid, b_uid]);
+        assert_eq!(world.consume_resource(&c_uid), Some((c_uid, State::Full as u8)));
+        assert_eq!(world.resource(&c_uid).unwrap().state(), State::High);
+
+        // Moving and removing resources relinks the network
+        world.move_resource(&c_uid, pos(2, 2, -4, &ctx));
+        assert_eq!(world.network().members(&c_uid), vec![c_uid, a_uid, b_uid]);
+        assert!(world.remove_resource(&b_uid).is_some());
+        assert_eq!(world.network().members(&a_uid), vec![a_uid, c_uid]);
+        assert!(world.network().links(&b_uid).is_empty());
+    }
+}
@@ -20,6 +20,16 @@ Purpose:
 
     Note - a single obstacle may occupy more than one hex cell.
 
+    Each cell of an obstacle has its own hit points, and abilities damage the
+    cells they strike as scaled by the elemental interaction matrix. Destroyed
+    cells are removed, and an obstacle broken apart in this way splits into
+    several obstacles, each of them contiguous.
+
+    Obstacles also react to one another as game time passes. Fire obstacles
+    set alight the adjacent cells of flammable obstacles, which burn down
+    and spread the fire further. Water obstacles douse adjacent flames, and
+    Ice obstacles melt beside them.
+
 \* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
 
 use crate::{
@@ -30,6 +40,8 @@ use crate::{
         Elemental
     },
     hex_directions,
+    mechanics::reaction::Mutation,
+    world::World,
     Plottable,
     Randomizable,
 };
@@ -45,15 +57,28 @@ use rand::Rng;
 /// Odds of terminating an obstacle on a given iteration
 const OBSTACLE_TERMINATION_ODDS: f32 = 0.05;
 
+/// Default hit points of each cell of an obstacle
+const DEFAULT_CELL_HEALTH: u32 = 20;
+
+/// Span of game time between each spread of fire among obstacles (in seconds)
+const BURN_INTERVAL_SECS: f64 = 6.0;
+
+/// Damage dealt to a cell by adjacent flames each burn interval, before elemental scaling
+const BURN_DAMAGE: u32 = 8;
+
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Data Structures
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Obstacle {
     uid:        [u8; 16],
     positions:  Vec<coords::Position>,
+    health:     Vec<u32>,       // Remaining hit points of each cell
+    burning:    Vec<bool>,      // Whether each cell is alight
+    max_health: u32,            // Hit points of each undamaged cell
+    flammable:  bool,           // Whether fire may spread to the obstacle
     element:    Element
 }
 
@@ -72,7 +97,7 @@ impl Obstacle {
     pub fn new(positions: Vec<coords::Position>, element: Element) -> Result<Self, ObstacleError> {
         // Verify that all positions in list are contiguous
         let mut prev_pos = positions.first().unwrap();
-        for pos in positions.iter() {
+        for pos in positions.iter().skip(1) {
             if pos.is_neighbor(prev_pos) {
                 prev_pos = pos;
             }
@@ -84,12 +109,35 @@ impl Obstacle {
 
         Ok(Self {
             uid:        *Uuid::new_v4().as_bytes(),
+            health:     vec![DEFAULT_CELL_HEALTH; positions.len()],
+            burning:    vec![false; positions.len()],
             positions,
+            max_health: DEFAULT_CELL_HEALTH,
+            flammable:  false,
             element,
         })
     }
 
 
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Sets the hit points of every cell, fully repairing the obstacle
+    pub fn with_health(mut self, max_health: u32) -> Self {
+        self.max_health = max_health;
+        self.health = vec![max_health; self.positions.len()];
+
+        self
+    }
+
+    pub fn with_flammability(mut self, flammable: bool) -> Self {
+        self.flammable = flammable;
+
+        self
+    }
+
+
     /*  *  *  *  *  *  *  *\
      *  Accessor Methods  *
     \*  *  *  *  *  *  *  */
@@ -101,6 +149,254 @@ impl Obstacle {
     pub fn positions(&self) -> &Vec<coords::Position> {
         &self.positions
     }
+
+    /// Returns the remaining hit points of the given cell, if the obstacle occupies it
+    pub fn health(&self, pos: &coords::Position) -> Option<u32> {
+        self.index_of(pos).map(|i| self.health[i])
+    }
+
+    pub fn max_health(&self) -> u32 {
+        self.max_health
+    }
+
+    pub fn is_flammable(&self) -> bool {
+        self.flammable
+    }
+
+    /// Determines if the given cell is alight. Every cell of a Fire obstacle is always alight.
+    pub fn is_burning(&self, pos: &coords::Position) -> bool {
+        self.index_of(pos).is_some_and(|i| self.element == Element::Fire || self.burning[i])
+    }
+
+    /// Returns every cell of the obstacle that is alight
+    pub fn burning_cells(&self) -> Vec<coords::Position> {
+        self.positions.iter()
+            .filter(|pos| self.is_burning(pos))
+            .cloned()
+            .collect()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Determines if the obstacle occupies the given position
+    pub fn contains(&self, pos: &coords::Position) -> bool {
+        self.positions.contains(pos)
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Removes the given cells from the obstacle.
+    /// The obstacle may be left noncontiguous, and should then be split.
+    pub fn remove_positions(&mut self, positions: &[coords::Position]) {
+        let kept: Vec<usize> = (0 .. self.positions.len())
+            .filter(|&i| !positions.contains(&self.positions[i]))
+            .collect();
+
+        self.positions = kept.iter().map(|&i| self.positions[i]).collect();
+        self.health = kept.iter().map(|&i| self.health[i]).collect();
+        self.burning = kept.iter().map(|&i| self.burning[i]).collect();
+    }
+
+    /// Damages each of the given cells by the given amount, removing any destroyed.
+    /// Returns the cells destroyed. The obstacle may be left noncontiguous, and should then be split.
+    pub fn damage(&mut self, positions: &[coords::Position], amount: u32) -> Vec<coords::Position> {
+        let mut destroyed = Vec::new();
+        for pos in positions {
+            if let Some(i) = self.index_of(pos) {
+                self.health[i] = self.health[i].saturating_sub(amount);
+                if self.health[i] == 0 {
+                    destroyed.push(*pos);
+                }
+            }
+        }
+
+        self.remove_positions(&destroyed);
+        destroyed
+    }
+
+    /// Sets the given cells alight, if the obstacle is flammable
+    pub fn ignite(&mut self, positions: &[coords::Position]) {
+        if !self.flammable {
+            return;
+        }
+
+        for pos in positions {
+            if let Some(i) = self.index_of(pos) {
+                self.burning[i] = true;
+            }
+        }
+    }
+
+    /// Puts out the given cells
+    pub fn extinguish(&mut self, positions: &[coords::Position]) {
+        for pos in positions {
+            if let Some(i) = self.index_of(pos) {
+                self.burning[i] = false;
+            }
+        }
+    }
+
+    /// Splits the obstacle into its connected groups of cells. The group containing the first cell keeps
+    /// the obstacle's UID, while the rest are given fresh UIDs. Each group keeps its cells in their original
+    /// order. An obstacle with no cells left splits into nothing.
+    pub fn split(self) -> Vec<Obstacle> {
+        // Flood fill outwards from each cell not yet grouped
+        let mut groups: Vec<Option<usize>> = vec![None; self.positions.len()];
+        let mut num_groups = 0;
+        for start in 0 .. self.positions.len() {
+            if groups[start].is_some() {
+                continue;
+            }
+
+            groups[start] = Some(num_groups);
+            let mut frontier = vec![start];
+            while let Some(i) = frontier.pop() {
+                for (j, cell) in self.positions.iter().enumerate() {
+                    if groups[j].is_none() && self.positions[i].is_neighbor(cell) {
+                        groups[j] = Some(num_groups);
+                        frontier.push(j);
+                    }
+                }
+            }
+            num_groups += 1;
+        }
+
+        let mut pieces: Vec<Obstacle> = (0 .. num_groups)
+            .map(|group| Obstacle {
+                uid:        if group == 0 { self.uid } else { *Uuid::new_v4().as_bytes() },
+                positions:  Vec::new(),
+                health:     Vec::new(),
+                burning:    Vec::new(),
+                max_health: self.max_health,
+                flammable:  self.flammable,
+                element:    self.element,
+            })
+            .collect();
+
+        for (i, group) in groups.into_iter().enumerate() {
+            // Every cell was grouped by the flood fill above
+            let piece = &mut pieces[group.unwrap()];
+            piece.positions.push(self.positions[i]);
+            piece.health.push(self.health[i]);
+            piece.burning.push(self.burning[i]);
+        }
+
+        pieces
+    }
+
+    /// Moves every cell of the obstacle by the given translation.
+    /// The obstacle is left untouched if any cell would leave the grid.
+    pub fn translate(&mut self, trans: &coords::Translation, ctx: &Context) -> Result<(), coords::CoordsError> {
+        let mut translated = self.positions.clone();
+        for pos in translated.iter_mut() {
+            pos.translate(trans, ctx)?;
+        }
+
+        self.positions = translated;
+        Ok(())
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Helper Methods    *
+    \*  *  *  *  *  *  *  */
+
+    fn index_of(&self, pos: &coords::Position) -> Option<usize> {
+        self.positions.iter().position(|cell| cell == pos)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Calculates the (whole) number of burn intervals elapsed over the given number of seconds plus any
+/// carried-over fraction, along with the remainder to be carried into the next update.
+pub fn burn_steps(elapsed_secs: f64, carry: f64) -> (u32, f64) {
+    let total = elapsed_secs.max(0.0) + carry;
+    let steps = (total / BURN_INTERVAL_SECS).floor();
+
+    (steps as u32, total - steps * BURN_INTERVAL_SECS)
+}
+
+/// Determines the damage the given attack deals to each obstacle occupying the given positions,
+/// scaled by the interaction of the attacking element with that of the obstacle
+pub fn evaluate_damage(world:       &World,
+                       attacker:    Element,
+                       positions:   &[coords::Position],
+                       magnitude:   usize) -> Vec<Mutation> {
+    let mut mutations = Vec::new();
+
+    for obstacle in world.obstacles() {
+        let hit_cells: Vec<coords::Position> = obstacle.positions().iter()
+            .filter(|pos| positions.contains(pos))
+            .cloned()
+            .collect();
+        let amount = (magnitude as f64 * world.interactions().multiplier(attacker, obstacle.element())) as u32;
+        if !hit_cells.is_empty() && amount > 0 {
+            mutations.push(Mutation::DamageObstacleCells(*obstacle.uid(), hit_cells, amount));
+        }
+    }
+
+    mutations
+}
+
+/// Determines the changes wrought by fire among the obstacles of the world over a single burn interval.
+/// Flames spread to adjacent flammable cells and burn them down, while Water obstacles douse
+/// adjacent flames and Ice obstacles melt beside them.
+pub fn evaluate_burn(world: &World) -> Vec<Mutation> {
+    let flames: Vec<coords::Position> = world.obstacles().iter()
+        .flat_map(|obstacle| obstacle.burning_cells())
+        .collect();
+    let water: Vec<coords::Position> = world.obstacles().iter()
+        .filter(|obstacle| obstacle.element() == Element::Water)
+        .flat_map(|obstacle| obstacle.positions().clone())
+        .collect();
+    let near_flames = |pos: &coords::Position| flames.iter().any(|flame| flame.is_neighbor(pos));
+    let near_water = |pos: &coords::Position| water.iter().any(|cell| cell.is_neighbor(pos));
+
+    let mut mutations = Vec::new();
+    for obstacle in world.obstacles() {
+        // Fire obstacles are the flames themselves, and neither burn down nor spread on their own account
+        if obstacle.element() == Element::Fire {
+            continue;
+        }
+        let uid = *obstacle.uid();
+
+        let (doused, alight): (Vec<coords::Position>, Vec<coords::Position>) = obstacle.burning_cells().into_iter()
+            .partition(|pos| near_water(pos));
+        if !doused.is_empty() {
+            mutations.push(Mutation::ExtinguishObstacleCells(uid, doused));
+        }
+
+        if obstacle.is_flammable() {
+            let kindled: Vec<coords::Position> = obstacle.positions().iter()
+                .filter(|pos| !obstacle.is_burning(pos) && near_flames(pos) && !near_water(pos))
+                .cloned()
+                .collect();
+            if !kindled.is_empty() {
+                mutations.push(Mutation::IgniteObstacleCells(uid, kindled));
+            }
+        }
+
+        let scorched: Vec<coords::Position> = obstacle.positions().iter()
+            .filter(|pos| alight.contains(pos) || (obstacle.element() == Element::Ice && near_flames(pos)))
+            .cloned()
+            .collect();
+        let amount = (BURN_DAMAGE as f64 * world.interactions().multiplier(Element::Fire, obstacle.element())) as u32;
+        if !scorched.is_empty() && amount > 0 {
+            mutations.push(Mutation::DamageObstacleCells(uid, scorched, amount));
+        }
+    }
+
+    mutations
 }
 
 
@@ -177,6 +473,165 @@ impl Randomizable for Obstacle {
         // Finally, generate a random element
         let element: Element = rng.gen();
 
-        Self {uid, positions, element}
+        Self {
+            uid,
+            health:     vec![DEFAULT_CELL_HEALTH; positions.len()],
+            burning:    vec![false; positions.len()],
+            positions,
+            max_health: DEFAULT_CELL_HEALTH,
+            flammable:  false,
+            element,
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::{
+        ability::{
+            Ability,
+            cast::{
+                self,
+                Target,
+            },
+        },
+        actor::Actor,
+        coords::tests::pos,
+    };
+
+    #[test]
+    fn destruction_and_splitting() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        let mut blast = Ability::new_name_only("Blast").with_range(3);
+        blast.set_element(Element::Fire);
+        blast.set_potency(30);
+        let blast_uid = *blast.uid();
+        let caster = Actor::new("Caster", pos(0, 0, 0, &ctx), 0, vec![blast]);
+        let caster_uid = *caster.uid();
+        world.add_actor(caster);
+
+        let cells = vec![pos(3, -2, -1, &ctx), pos(3, -1, -2, &ctx), pos(3, 0, -3, &ctx), pos(3, 1, -4, &ctx), pos(3, 2, -5, &ctx)];
+        let wall = Obstacle::new(cells.clone(), Element::Earth).unwrap();
+        let wall_uid = *wall.uid();
+        world.add_obstacle(wall);
+
+        // Fire is less effective against earth, but still enough to break through the middle of the wall
+        let outcome = cast::execute(&mut world, &caster_uid, &blast_uid, &Target::Cell(cells[2])).unwrap();
+        assert_eq!(outcome.mutations, vec![Mutation::DamageObstacleCells(wall_uid, vec![cells[2]], 22)]);
+        assert_eq!(world.obstacles().len(), 2);
+        assert_eq!(world.obstacle(&wall_uid).unwrap().positions(), &cells[..2].to_vec());
+        assert_eq!(world.obstacles()[1].positions(), &cells[3..].to_vec());
+        assert_eq!(world.obstacles()[1].health(&cells[3]), Some(DEFAULT_CELL_HEALTH));
+
+        // Weaker blows only wear the cells down
+        world.apply_mutation(&Mutation::DamageObstacleCells(wall_uid, vec![cells[0]], 5));
+        assert_eq!(world.obstacle(&wall_uid).unwrap().health(&cells[0]), Some(15));
+        assert_eq!(world.obstacles().len(), 2);
+    }
+
+    #[test]
+    fn splitting_by_connectivity() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        let ring = vec![pos(1, -1, 0, &ctx), pos(1, 0, -1, &ctx), pos(0, 1, -1, &ctx),
+                        pos(-1, 1, 0, &ctx), pos(-1, 0, 1, &ctx), pos(0, -1, 1, &ctx)];
+        let wall = Obstacle::new(ring.clone(), Element::Earth).unwrap();
+        let wall_uid = *wall.uid();
+        world.add_obstacle(wall);
+
+        // Breaking a ring leaves it whole, though its remaining cells no longer run in order
+        world.apply_mutation(&Mutation::DamageObstacleCells(wall_uid, vec![ring[1]], DEFAULT_CELL_HEALTH));
+        assert_eq!(world.obstacles().len(), 1);
+        assert_eq!(world.obstacle(&wall_uid).unwrap().positions(), &vec![ring[0], ring[2], ring[3], ring[4], ring[5]]);
+
+        // Breaking it again splits off the isolated cell, with the rest kept together
+        world.apply_mutation(&Mutation::DamageObstacleCells(wall_uid, vec![ring[3]], DEFAULT_CELL_HEALTH));
+        assert_eq!(world.obstacles().len(), 2);
+        assert_eq!(world.obstacle(&wall_uid).unwrap().positions(), &vec![ring[0], ring[4], ring[5]]);
+        assert_eq!(world.obstacles()[1].positions(), &vec![ring[2]]);
+    }
+
+    #[test]
+    fn pushing() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        let cells = vec![pos(0, 0, 0, &ctx), pos(1, 0, -1, &ctx)];
+        let boulder = Obstacle::new(cells, Element::Earth).unwrap();
+        let boulder_uid = *boulder.uid();
+        world.add_obstacle(boulder);
+
+        // Pushing along its own length moves the obstacle into the cells it vacates
+        world.apply_mutation(&Mutation::PushObstacle(boulder_uid, hex_directions::Side::NorthEast));
+        let moved = vec![pos(1, 0, -1, &ctx), pos(2, 0, -2, &ctx)];
+        assert_eq!(world.obstacle(&boulder_uid).unwrap().positions(), &moved);
+
+        // An actor in the way blocks the push
+        world.add_actor(Actor::new("Bystander", pos(3, 0, -3, &ctx), 0, Vec::new()));
+        assert!(!world.can_push_obstacle(&boulder_uid, hex_directions::Side::NorthEast));
+        world.apply_mutation(&Mutation::PushObstacle(boulder_uid, hex_directions::Side::NorthEast));
+        assert_eq!(world.obstacle(&boulder_uid).unwrap().positions(), &moved);
+
+        // As does another obstacle
+        assert!(world.can_push_obstacle(&boulder_uid, hex_directions::Side::South));
+        world.add_obstacle(Obstacle::new(vec![pos(1, -1, 0, &ctx)], Element::Ice).unwrap());
+        assert!(!world.can_push_obstacle(&boulder_uid, hex_directions::Side::South));
+        world.apply_mutation(&Mutation::PushObstacle(boulder_uid, hex_directions::Side::South));
+        assert_eq!(world.obstacle(&boulder_uid).unwrap().positions(), &moved);
+    }
+
+    #[test]
+    fn fire_spread() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        let hedge_cells = vec![pos(1, 0, -1, &ctx), pos(2, 0, -2, &ctx), pos(3, 0, -3, &ctx)];
+        let brazier = Obstacle::new(vec![pos(0, 0, 0, &ctx)], Element::Fire).unwrap();
+        let hedge = Obstacle::new(hedge_cells.clone(), Element::Earth).unwrap().with_flammability(true);
+        let hedge_uid = *hedge.uid();
+        let ice = Obstacle::new(vec![pos(0, 1, -1, &ctx)], Element::Ice).unwrap();
+        let ice_uid = *ice.uid();
+        let pool = Obstacle::new(vec![pos(4, 0, -4, &ctx)], Element::Water).unwrap();
+        world.add_obstacle(brazier);
+        world.add_obstacle(hedge);
+        world.add_obstacle(ice);
+        world.add_obstacle(pool);
+
+        // The brazier sets the hedge alight, and melts the ice beside it
+        world.advance(Duration::from_secs(6));
+        assert_eq!(world.obstacle(&hedge_uid).unwrap().burning_cells(), hedge_cells[..1].to_vec());
+        assert_eq!(world.obstacle(&ice_uid).unwrap().health(&pos(0, 1, -1, &ctx)), Some(4));
+
+        // Fire creeps along the hedge, burning it down, but the pool keeps the far end from catching
+        world.advance(Duration::from_secs(12));
+        let hedge = world.obstacle(&hedge_uid).unwrap();
+        assert_eq!(hedge.burning_cells(), hedge_cells[..2].to_vec());
+        assert_eq!(hedge.health(&hedge_cells[0]), Some(8));
+        assert_eq!(hedge.health(&hedge_cells[1]), Some(14));
+        assert!(world.obstacle(&ice_uid).is_none());
+
+        // Water puts the flames out
+        world.apply_mutation(&Mutation::ExtinguishObstacleCells(hedge_uid, hedge_cells.clone()));
+        world.advance(Duration::from_secs(6));
+        let hedge = world.obstacle(&hedge_uid).unwrap();
+        assert_eq!(hedge.burning_cells(), hedge_cells[..1].to_vec());
+        assert_eq!(hedge.health(&hedge_cells[1]), Some(14));
+
+        // Burning through the hedge leaves what remains of it
+        world.advance(Duration::from_secs(12));
+        let hedge = world.obstacle(&hedge_uid).unwrap();
+        assert_eq!(hedge.positions(), &hedge_cells[1..].to_vec());
     }
 }
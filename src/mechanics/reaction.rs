@@ -0,0 +1,228 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : mechanics/reaction.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the reaction engine, which determines how the world
+    changes when elements meet.
+
+    The element of an ability (and of any active weather) is evaluated
+    against the elements of the obstacles and resources at the affected
+    positions using the world's interaction matrix. The resulting mutations
+    are returned for the world to apply, rather than being applied directly.
+
+    Strong weather also acts on its own as the world advances, scattering the
+    obstacles its element scatters in the direction the weather blows.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use crate::{
+    coords,
+    element::{
+        Element,
+        Elemental,
+        interaction::Reaction,
+    },
+    hex_directions,
+    mechanics::weather::Intensity,
+    world::World,
+};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the changes a reaction may make to the world
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mutation {
+    RemoveObstacleCells([u8; 16], Vec<coords::Position>),
+    DamageObstacleCells([u8; 16], Vec<coords::Position>, u32),  // Obstacle, cells struck, damage to each
+    IgniteObstacleCells([u8; 16], Vec<coords::Position>),
+    ExtinguishObstacleCells([u8; 16], Vec<coords::Position>),
+    PushObstacle([u8; 16], hex_directions::Side),
+    ConsumeResource([u8; 16]),
+    ReplenishResource([u8; 16], u8),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Evaluates the given element, along with any active weather, against the obstacles and resources
+/// at the given positions. Obstacles are pushed in the facing direction when scattered.
+pub fn evaluate(world:      &World,
+                element:    Element,
+                positions:  &[coords::Position],
+                facing:     hex_directions::Side) -> Vec<Mutation> {
+    let mut mutations = Vec::new();
+
+    // Weather only reacts once it has built to a noticeable intensity
+    let mut attackers = vec![element];
+    let weather = world.weather();
+    if weather.intensity(world.tick()) != Intensity::None && weather.element() != element {
+        attackers.push(weather.element());
+    }
+
+    for attacker in attackers {
+        for mutation in evaluate_obstacles(world, attacker, positions, facing) {
+            if !mutations.contains(&mutation) {
+                mutations.push(mutation);
+            }
+        }
+        for mutation in evaluate_resources(world, attacker, positions) {
+            if !mutations.contains(&mutation) {
+                mutations.push(mutation);
+            }
+        }
+    }
+
+    mutations
+}
+
+/// Evaluates a single gust of the world's weather against every obstacle, pushing those it scatters
+/// in the direction the weather blows. Only strong weather gusts.
+pub fn evaluate_weather(world: &World) -> Vec<Mutation> {
+    let weather = world.weather();
+    if !weather.intensity(world.tick()).is_strong() {
+        return Vec::new();
+    }
+
+    world.obstacles().iter()
+        .filter(|obstacle| world.interactions().reaction(weather.element(), obstacle.element()) == Reaction::Scatter)
+        .map(|obstacle| Mutation::PushObstacle(*obstacle.uid(), weather.direction()))
+        .collect()
+}
+
+/// Evaluates the given attacking element against all obstacles occupying the given positions
+pub fn evaluate_obstacles(world:        &World,
+                          attacker:     Element,
+                          positions:    &[coords::Position],
+                          facing:       hex_directions::Side) -> Vec<Mutation> {
+    let mut mutations = Vec::new();
+
+    for obstacle in world.obstacles() {
+        let hit_cells: Vec<coords::Position> = obstacle.positions().iter()
+            .filter(|pos| positions.contains(pos))
+            .cloned()
+            .collect();
+        if hit_cells.is_empty() {
+            continue;
+        }
+
+        // Fire sets flammable obstacles alight, and Water puts them out
+        if obstacle.is_flammable() {
+            match attacker {
+                Element::Fire   => mutations.push(Mutation::IgniteObstacleCells(*obstacle.uid(), hit_cells.clone())),
+                Element::Water  => mutations.push(Mutation::ExtinguishObstacleCells(*obstacle.uid(), hit_cells.clone())),
+                _               => {},
+            }
+        }
+
+        match world.interactions().reaction(attacker, obstacle.element()) {
+            Reaction::Melt          |
+            Reaction::Evaporate     |
+            Reaction::Extinguish    |
+            Reaction::Illuminate    |
+            Reaction::Obscure       => mutations.push(Mutation::RemoveObstacleCells(*obstacle.uid(), hit_cells)),
+            Reaction::Scatter       => mutations.push(Mutation::PushObstacle(*obstacle.uid(), facing)),
+            _                       => {},
+        }
+    }
+
+    mutations
+}
+
+/// Evaluates the given attacking element against all resources whose radius covers any of the given positions
+pub fn evaluate_resources(world:        &World,
+                          attacker:     Element,
+                          positions:    &[coords::Position]) -> Vec<Mutation> {
+    let mut mutations = Vec::new();
+
+    for resource in world.resources() {
+        if !positions.iter().any(|pos| resource.covers(pos)) {
+            continue;
+        }
+
+        match world.interactions().reaction(attacker, resource.element()) {
+            Reaction::Conduct       |
+            Reaction::Ignite        => mutations.push(Mutation::ReplenishResource(*resource.uid(), 1)),
+            Reaction::Freeze        |
+            Reaction::Melt          |
+            Reaction::Evaporate     |
+            Reaction::Extinguish    |
+            Reaction::Illuminate    |
+            Reaction::Obscure       => mutations.push(Mutation::ConsumeResource(*resource.uid())),
+            _                       => {},
+        }
+    }
+
+    mutations
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::{
+        actor::Actor,
+        context::Context,
+        coords::tests::pos,
+        mechanics::{
+            obstacle::Obstacle,
+            weather,
+        },
+        polyfunc::PolyFunc,
+    };
+
+    #[test]
+    fn weather_gusts() {
+        let mut world = World::new(Context::default());
+        let ctx = world.ctx().clone();
+
+        let gale = weather::Event::new(Element::Wind, PolyFunc::new(255.0, 100.0, 0.0))
+            .with_direction(hex_directions::Side::NorthEast);
+        world.set_weather(gale);
+
+        let floe = Obstacle::new(vec![pos(0, 0, 0, &ctx)], Element::Ice).unwrap();
+        let floe_uid = *floe.uid();
+        let boulder = Obstacle::new(vec![pos(0, 3, -3, &ctx)], Element::Earth).unwrap();
+        let boulder_uid = *boulder.uid();
+        world.add_obstacle(floe);
+        world.add_obstacle(boulder);
+        world.add_actor(Actor::new("Bystander", pos(4, 0, -4, &ctx), 0, Vec::new()));
+
+        // Weather too weak to gust leaves everything in place
+        world.advance(Duration::from_secs(6));
+        assert!(evaluate_weather(&world).is_empty());
+        assert_eq!(world.obstacle(&floe_uid).unwrap().positions(), &vec![pos(0, 0, 0, &ctx)]);
+
+        // Strong wind scatters the ice with each gust, without any ability being cast, until an actor blocks it
+        world.advance(Duration::from_secs(12));
+        assert!(world.weather().intensity(world.tick()).is_strong());
+        assert_eq!(world.obstacle(&floe_uid).unwrap().positions(), &vec![pos(2, 0, -2, &ctx)]);
+        world.advance(Duration::from_secs(12));
+        assert_eq!(world.obstacle(&floe_uid).unwrap().positions(), &vec![pos(3, 0, -3, &ctx)]);
+        assert_eq!(world.obstacle(&boulder_uid).unwrap().positions(), &vec![pos(0, 3, -3, &ctx)]);
+    }
+}
@@ -19,8 +19,19 @@ Purpose:
     can be things like campfires, ponds, etc. that enhance elementally-aligned
     abilities for actors within its radius.
 
+    Resources change with the game clock. Each state short of Full regrows
+    towards the next after its own interval, while an Overflowing resource
+    decays back to Full. A resource drawn down to Depleted lies dormant for a
+    cooldown before regrowth begins. The radius of a resource follows its
+    state, reaching its base radius when Full.
+
+    Resources may be controlled by a faction or actor, and do not regrow
+    while their control is contested.
+
 \* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
 
+use std::time::Duration;
+
 use crate::{
     context::Context,
     coords,
@@ -28,10 +39,15 @@ use crate::{
         Element,
         Elemental
     },
+    mechanics::control::{
+        Control,
+        Controller,
+    },
     Plottable,
     Randomizable,
 };
 
+use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use rand::{
     Rng,
@@ -42,22 +58,44 @@ use rand::{
 };
 
 
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default span of game time for a resource to regrow by one state (in seconds)
+const DEFAULT_REGROWTH_SECS: f64 = 6.0;
+
+/// Default span of game time for an overflowing resource to decay back to full (in seconds)
+const DEFAULT_DECAY_SECS: f64 = 6.0;
+
+/// Default span of game time a depleted resource lies dormant before regrowing (in seconds)
+const DEFAULT_DEPLETION_COOLDOWN_SECS: f64 = 12.0;
+
+/// Bonus potency granted to empowered abilities for each level of a resource's state
+const POTENCY_BONUS_PER_STATE: f64 = 0.1;
+
+
 ///////////////////////////////////////////////////////////////////////////////
 //  Data Structures
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Resource {
-    uid:        [u8; 16],
-    element:    Element,
-    state:      State,
-    origin:     coords::Position,
-    radius:     usize,
+    uid:            [u8; 16],
+    element:        Element,
+    state:          State,
+    origin:         coords::Position,
+    base_radius:    usize,      // Radius when Full
+    dynamics:       Dynamics,
+    progress:       f64,        // Game time spent towards the next change of state (in seconds)
+    dormancy:       f64,        // Game time remaining before a depleted resource may regrow (in seconds)
+    control:        Control,
 }
 
 // OPT: *PERFORMANCE* Do custom enums end up on the stack? if not, remove the Copy/Clone derivations
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Default)]
 pub enum State {
+    #[default]
     Depleted    = 0,
     Low         = 1,
     Partial     = 2,
@@ -66,24 +104,60 @@ pub enum State {
     Overflow    = 5,
 }
 
+/// Time-driven behaviour of a resource. Spans are given in seconds of game time, and
+/// a span of None prevents the corresponding change from happening on its own.
+#[derive(
+    Debug,
+    Copy, Clone,
+    PartialEq,
+    Serialize, Deserialize
+)]
+pub struct Dynamics {
+    pub regrowth:           [Option<f64>; 4],   // Span to regrow from each of Depleted, Low, Partial, and High
+    pub decay:              Option<f64>,        // Span to decay from Overflow back to Full
+    pub depletion_cooldown: f64,                // Span a depleted resource lies dormant before regrowing
+}
+
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Object Implementation
 ///////////////////////////////////////////////////////////////////////////////
 
 impl Resource {
-    /// Fully-qualified constructor
+    /// Fully-qualified constructor. The given radius is that of the resource when Full.
     pub fn new(element: Element, state: State, origin: coords::Position, radius: usize) -> Self {
         Self {
-            uid:        *Uuid::new_v4().as_bytes(),
+            uid:            *Uuid::new_v4().as_bytes(),
             element,
             state,
             origin,
-            radius,
+            base_radius:    radius,
+            dynamics:       Dynamics::default(),
+            progress:       0.0,
+            dormancy:       0.0,
+            control:        Control::default(),
         }
     }
 
 
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn with_dynamics(mut self, dynamics: Dynamics) -> Self {
+        self.dynamics = dynamics;
+
+        self
+    }
+
+    /// Sets the span of game time a lone claimant must hold the resource to gain control (in seconds)
+    pub fn with_hold_secs(mut self, hold_secs: f64) -> Self {
+        self.control.set_hold_secs(hold_secs);
+
+        self
+    }
+
+
     /*  *  *  *  *  *  *  *\
      *  Accessor Methods  *
     \*  *  *  *  *  *  *  */
@@ -96,8 +170,55 @@ impl Resource {
         self.state
     }
 
+    pub fn base_radius(&self) -> usize {
+        self.base_radius
+    }
+
+    /// Returns the current radius, which scales with state: nothing when Depleted,
+    /// the base radius when Full, and beyond it when Overflowing
     pub fn radius(&self) -> usize {
-        self.radius
+        (self.base_radius * self.state as usize).div_ceil(State::Full as usize)
+    }
+
+    pub fn dynamics(&self) -> &Dynamics {
+        &self.dynamics
+    }
+
+    /// Returns the game time remaining before a depleted resource may regrow (in seconds)
+    pub fn dormancy(&self) -> f64 {
+        self.dormancy
+    }
+
+    pub fn control(&self) -> &Control {
+        &self.control
+    }
+
+    pub fn controller(&self) -> Option<&Controller> {
+        self.control.controller()
+    }
+
+    pub fn is_contested(&self) -> bool {
+        self.control.is_contested()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Determines if the given position lies within the resource's radius
+    pub fn covers(&self, pos: &coords::Position) -> bool {
+        self.origin.distance_to(pos) as usize <= self.radius()
+    }
+
+    /// Determines if the resource empowers abilities of the given element
+    pub fn empowers(&self, element: Element) -> bool {
+        element != Element::Unset && element == self.element && self.state != State::Depleted
+    }
+
+    /// Multiplier applied to the potency of abilities empowered by the resource in its current state
+    pub fn potency_multiplier(&self) -> f64 {
+        1.0 + self.state as u8 as f64 * POTENCY_BONUS_PER_STATE
     }
 
     
@@ -120,6 +241,14 @@ impl Resource {
             State::Overflow => self.state = State::Full,
         }
 
+        // regrowth starts afresh from the new state, after a cooldown if newly depleted
+        if initial_state != self.state {
+            self.progress = 0.0;
+            if self.state == State::Depleted {
+                self.dormancy = self.dynamics.depletion_cooldown;
+            }
+        }
+
         // return the magnitude based on the initial state
         match initial_state {
             State::Depleted => None,
@@ -131,7 +260,7 @@ impl Resource {
     pub fn replenish(&mut self, mag: u8) {
 
         // set the state to the initial + given magnitude
-        let state_val = (self.state as u8) + mag;
+        let state_val = (self.state as u8).saturating_add(mag);
         match state_val {
             0 => self.state = State::Depleted,
             1 => self.state = State::Low,
@@ -140,16 +269,67 @@ impl Resource {
             4 => self.state = State::Full,
             _ => self.state = State::Overflow,
         }
+
+        if mag > 0 {
+            self.progress = 0.0;
+            self.dormancy = 0.0;
+        }
+    }
+
+    pub fn set_dynamics(&mut self, dynamics: Dynamics) {
+        self.dynamics = dynamics;
+    }
+
+    pub fn set_origin(&mut self, origin: coords::Position) {
+        self.origin = origin;
     }
 
-    // Increases the radius of the resource
-    pub fn intensify(&mut self, mag: usize) {
-        self.radius += mag;
+    pub fn set_controller(&mut self, controller: Option<Controller>) {
+        self.control.set_controller(controller);
     }
 
-    // Decreases the radius of the resource
-    pub fn weaken(&mut self, mag: usize) {
-        self.radius -= mag;
+    /// Updates control of the resource given the parties currently claiming it.
+    /// Returns true if control changed hands.
+    pub fn update_control(&mut self, claimants: &[Controller], elapsed: Duration) -> bool {
+        self.control.update(claimants, elapsed.as_secs_f64())
+    }
+
+    /// Advances the resource by the given span of game time, regrowing or decaying as its
+    /// dynamics dictate. Multiple changes of state may occur over a long enough span.
+    /// Contested resources do not regrow, though they still decay and wait out dormancy.
+    pub fn update(&mut self, elapsed: Duration) {
+        let mut remaining = elapsed.as_secs_f64();
+
+        while remaining > 0.0 {
+            // Depleted resources must first wait out their cooldown
+            if self.dormancy > 0.0 {
+                let waited = f64::min(self.dormancy, remaining);
+                self.dormancy -= waited;
+                remaining -= waited;
+                continue;
+            }
+
+            let (span, next) = match self.state {
+                State::Full     => break,
+                State::Overflow => (self.dynamics.decay, State::Full),
+                _ if self.control.is_contested() => break,
+                state           => (self.dynamics.regrowth[state as usize], State::from(state as u8 + 1)),
+            };
+            let span = match span {
+                Some(span)  => span,
+                None        => break,
+            };
+
+            let needed = span - self.progress;
+            if remaining < needed {
+                self.progress += remaining;
+                break;
+            }
+
+            remaining -= f64::max(needed, 0.0);
+            self.state = next;
+            self.progress = 0.0;
+        }
     }
 }
 
@@ -164,11 +344,15 @@ impl Resource {
 impl Default for Resource {
     fn default() -> Self {
         Self {
-            uid:        *Uuid::new_v4().as_bytes(),
-            element:    Element::default(),
-            state:      State::default(),
-            origin:     coords::Position::default(),
-            radius:     0,
+            uid:            *Uuid::new_v4().as_bytes(),
+            element:        Element::default(),
+            state:          State::default(),
+            origin:         coords::Position::default(),
+            base_radius:    0,
+            dynamics:       Dynamics::default(),
+            progress:       0.0,
+            dormancy:       0.0,
+            control:        Control::default(),
         }
     }
 }
@@ -205,20 +389,33 @@ impl Randomizable for Resource {
             element,
             state,
             origin,
-            radius,
+            base_radius:    radius,
+            dynamics:       Dynamics::default(),
+            progress:       0.0,
+            dormancy:       0.0,
+            control:        Control::default(),
         }
     }
 }
 
 
 /*  *  *  *  *  *  *  *\
- *       State        *
+ *      Dynamics      *
 \*  *  *  *  *  *  *  */
-impl Default for State {
+impl Default for Dynamics {
     fn default() -> Self {
-        Self::Depleted
+        Self {
+            regrowth:           [Some(DEFAULT_REGROWTH_SECS); 4],
+            decay:              Some(DEFAULT_DECAY_SECS),
+            depletion_cooldown: DEFAULT_DEPLETION_COOLDOWN_SECS,
+        }
     }
 }
+
+
+/*  *  *  *  *  *  *  *\
+ *       State        *
+\*  *  *  *  *  *  *  */
 impl From<u8> for State {
     fn from(val: u8) -> Self {
         match val {
@@ -239,3 +436,48 @@ impl Distribution<State> for Standard {
         State::from((rand_num % State::Overflow as u8) + 1)
     }
 }
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dynamics() {
+        let dynamics = Dynamics {
+            regrowth:           [Some(4.0), Some(2.0), Some(2.0), None],
+            decay:              Some(3.0),
+            depletion_cooldown: 5.0,
+        };
+        let mut resource = Resource::new(Element::Fire, State::Low, coords::Position::default(), 4)
+            .with_dynamics(dynamics);
+        assert_eq!(resource.radius(), 1);
+
+        // Drawing the last of the resource leaves it dormant before regrowing
+        resource.consume();
+        assert_eq!(resource.radius(), 0);
+        resource.update(Duration::from_secs(8));
+        assert_eq!(resource.state(), State::Depleted);
+        resource.update(Duration::from_secs(1));
+        assert_eq!(resource.state(), State::Low);
+
+        // Several states may be regrown at once, stopping where regrowth is disabled
+        resource.update(Duration::from_secs(10));
+        assert_eq!(resource.state(), State::High);
+        assert_eq!(resource.radius(), 3);
+
+        // Overflowing resources swell, then decay back to full
+        resource.replenish(2);
+        assert_eq!(resource.state(), State::Overflow);
+        assert_eq!(resource.radius(), 5);
+        resource.update(Duration::from_secs(2));
+        assert_eq!(resource.state(), State::Overflow);
+        resource.update(Duration::from_secs(1));
+        assert_eq!(resource.state(), State::Full);
+        assert_eq!(resource.radius(), 4);
+    }
+}
@@ -22,6 +22,9 @@ Purpose:
 
     Effects follow a defined polynomial curve in severity.
 
+    Weather blows in a direction, and strong weather gusts at regular
+    intervals, scattering any obstacles it scatters on contact.
+
 \* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
 
 use std::time::Duration;
@@ -32,6 +35,7 @@ use crate::{
         Element,
         Elemental,
     },
+    hex_directions,
     polyfunc::PolyFunc,
     Randomizable,
 };
@@ -53,19 +57,24 @@ const STRONG_INTENSITY_RANGE_MAX:   i64 = 191;
 const SEVERE_INTENSITY_RANGE_MIN:   i64 = 192;
 const SEVERE_INTENSITY_RANGE_MAX:   i64 = 255;
 
+/// Span of game time between each gust of strong weather (in seconds)
+const GUST_INTERVAL_SECS: f64 = 6.0;
+
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Data Structures
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Event {
     element:    Element,
     function:   PolyFunc,
+    direction:  hex_directions::Side,   // Direction in which the weather blows
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Default)]
 pub enum Intensity {
+    #[default]
     None,
     Mild,
     Strong,
@@ -81,7 +90,11 @@ pub enum Intensity {
 impl Event {
     /// Fully-qualified constructor. You probably don't want to use this.
     pub fn new(element: Element, function: PolyFunc) -> Self {
-        Self {element, function}
+        Self {
+            element,
+            function,
+            direction:  hex_directions::Side::default(),
+        }
     }
 
 
@@ -95,6 +108,12 @@ impl Event {
         self
     }
 
+    pub fn with_direction(mut self, direction: hex_directions::Side) -> Self {
+        self.direction = direction;
+
+        self
+    }
+
 
     /*  *  *  *  *  *  *  *\
      *  Mutator Methods   *
@@ -121,6 +140,10 @@ impl Event {
     pub fn duration(&self) -> Duration {
         Duration::from_secs_f64(self.function.duration())
     }
+
+    pub fn direction(&self) -> hex_directions::Side {
+        self.direction
+    }
 }
 
 impl Intensity {
@@ -134,6 +157,25 @@ impl Intensity {
             Intensity::Max      => 1.000,
         }
     }
+
+    /// Determines if weather of this intensity is strong enough to gust
+    pub fn is_strong(&self) -> bool {
+        matches!(self, Intensity::Strong | Intensity::Severe | Intensity::Max)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Calculates the (whole) number of gust intervals elapsed over the given number of seconds plus any
+/// carried-over fraction, along with the remainder to be carried into the next update.
+pub fn gust_steps(elapsed_secs: f64, carry: f64) -> (u32, f64) {
+    let total = elapsed_secs.max(0.0) + carry;
+    let steps = (total / GUST_INTERVAL_SECS).floor();
+
+    (steps as u32, total - steps * GUST_INTERVAL_SECS)
 }
 
 
@@ -144,11 +186,6 @@ impl Intensity {
 /*  *  *  *  *  *  *  *\
  *     Intensity      *
 \*  *  *  *  *  *  *  */
-impl Default for Intensity {
-    fn default() -> Self {
-        Self::None
-    }
-}
 impl From<i64> for Intensity {
     fn from(src: i64) -> Self {
         match src {
@@ -188,7 +225,8 @@ impl Randomizable for Event {
 
         let element: Element = rng.gen();
         let function = PolyFunc::rand_constrained(ctx.max_weather_intensity(), ctx.max_weather_duration());
+        let direction: hex_directions::Side = rng.gen();
 
-        Self {element, function}
+        Self {element, function, direction}
     }
 }
@@ -29,13 +29,18 @@ Purpose:
 use std::fmt;
 
 use rand::Rng;
+use serde::{Serialize, Deserialize};
 
 
 ///////////////////////////////////////////////////////////////////////////////
 //  Data Structures
 ///////////////////////////////////////////////////////////////////////////////
 
-#[derive(Default)]
+#[derive(
+    Default,
+    Copy, Clone,
+    Serialize, Deserialize
+)]
 pub struct PolyFunc {
     magnitude:  f64,
     duration:   f64,
@@ -81,6 +86,10 @@ impl PolyFunc {
      *  Accessor Methods  *
     \*  *  *  *  *  *  *  */
 
+    pub fn magnitude(&self) -> f64 {
+        self.magnitude
+    }
+
     pub fn duration(&self) -> f64 {
         self.duration
     }
@@ -94,6 +103,10 @@ impl PolyFunc {
      *  Mutator Methods   *
     \*  *  *  *  *  *  *  */
 
+    pub fn set_magnitude(&mut self, magnitude: f64) {
+        self.magnitude = magnitude;
+    }
+
     pub fn set_duration(&mut self, duration: f64) {
         self.duration = duration;
     }
@@ -115,6 +128,23 @@ impl PolyFunc {
 
         -a * (tick - b) * (tick - c)
     }
+
+    // Integrates the polynomial function between the given game time ticks, counting only
+    // the span during which the function is active (from start_time to start_time + duration)
+    pub fn integrate(&self, from: f64, to: f64) -> f64 {
+        let b: f64 = self.start_time;
+        let c: f64 = self.start_time + self.duration;
+        let from = from.max(b);
+        let to = to.min(c);
+        if to <= from || self.duration <= 0.0 {
+            return 0.0;
+        }
+
+        let a: f64 = self.magnitude / (self.duration / 2.0).powi(2);
+        let antiderivative = |x: f64| -a * (x.powi(3) / 3.0 - (b + c) * x.powi(2) / 2.0 + b * c * x);
+
+        antiderivative(to) - antiderivative(from)
+    }
 }
 
 
@@ -0,0 +1,623 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : turn.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the turn scheduler, which layers turn-based play on
+    top of the continuous game clock.
+
+    Each round, actors take turns in order of initiative. An actor spends
+    action points to move and cast during its turn, and may instead delay
+    its turn until the end of the round, or ready an action to be taken
+    out of turn when a trigger occurs.
+
+    The game clock stands still during a round. At each round boundary the
+    world is advanced by a fixed span of game time, so that time-driven
+    state (weather, cooldowns, statuses, fatigue, resources) progresses.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    time::Duration,
+};
+
+use crate::{
+    ability::cast::{
+        self,
+        CastError,
+        Outcome,
+        Target,
+    },
+    coords,
+    hex_directions,
+    mechanics::movement::{
+        self,
+        MovementError,
+    },
+    world::World,
+    Plottable,
+};
+
+use uuid::Uuid;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Named Constants
+///////////////////////////////////////////////////////////////////////////////
+
+/// Default number of action points granted to each actor per turn
+const DEFAULT_ACTION_POINTS: u32 = 4;
+
+/// Default action point cost of moving a single cell
+const DEFAULT_MOVE_COST: u32 = 1;
+
+/// Default action point cost of casting an ability
+const DEFAULT_CAST_COST: u32 = 2;
+
+/// Default span of game time that passes between rounds (in seconds)
+const DEFAULT_ROUND_SECS: u64 = 6;
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+/// Enumeration of the actions an actor may take during its turn
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Move(hex_directions::Side),
+    Cast([u8; 16], Target),
+}
+
+/// Enumeration of the events that may trigger a readied action
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Trigger {
+    Approach(usize),        // Another actor moves to within the given distance of the readied actor
+    Cast,                   // Another actor casts an ability
+    ActorActs([u8; 16]),    // The given actor takes any action
+}
+
+/// An action readied by an actor, to be taken when its trigger occurs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Readied {
+    pub actor:      [u8; 16],
+    pub action:     Action,
+    pub trigger:    Trigger,
+}
+
+/// Report of a successfully performed action
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionReport {
+    Moved(coords::Position),
+    Cast(Outcome),
+}
+
+/// Report of a readied action taken in response to its trigger
+#[derive(Debug)]
+pub struct Triggered {
+    pub readied:    Readied,
+    pub result:     Result<ActionReport, TurnError>,
+}
+
+/// Report of an action taken during a turn, along with any readied actions it triggered
+#[derive(Debug)]
+pub struct Report {
+    pub action:     ActionReport,
+    pub triggered:  Vec<Triggered>,
+}
+
+/// Turn-based scheduler for the actors of a world
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    action_points:  u32,                        // Action points granted per turn
+    move_cost:      u32,
+    cast_cost:      u32,
+    round_duration: Duration,                   // Game time that passes between rounds
+    round:          u32,
+    order:          Vec<[u8; 16]>,              // Turn order for the current round
+    current:        usize,                      // Index into order of the acting actor
+    points:         u32,                        // Action points remaining to the acting actor
+    delayed:        HashMap<[u8; 16], u32>,     // Actors that have delayed this round, and their banked points
+    readied:        Vec<Readied>,
+}
+
+#[derive(Debug)]
+pub enum TurnError {
+    NoActiveActor,
+    UnknownActor([u8; 16]),
+    InsufficientPoints(u32, u32),
+    AlreadyDelayed([u8; 16]),
+    Movement(MovementError),
+    Cast(CastError),
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl Scheduler {
+    /// Constructs a scheduler with default costs. No round is in progress until begin_round() is called.
+    pub fn new() -> Self {
+        Self {
+            action_points:  DEFAULT_ACTION_POINTS,
+            move_cost:      DEFAULT_MOVE_COST,
+            cast_cost:      DEFAULT_CAST_COST,
+            round_duration: Duration::from_secs(DEFAULT_ROUND_SECS),
+            round:          0,
+            order:          Vec::new(),
+            current:        0,
+            points:         0,
+            delayed:        HashMap::new(),
+            readied:        Vec::new(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Builder Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn with_action_points(mut self, action_points: u32) -> Self {
+        self.action_points = action_points;
+
+        self
+    }
+
+    pub fn with_costs(mut self, move_cost: u32, cast_cost: u32) -> Self {
+        self.move_cost = move_cost;
+        self.cast_cost = cast_cost;
+
+        self
+    }
+
+    pub fn with_round_duration(mut self, round_duration: Duration) -> Self {
+        self.round_duration = round_duration;
+
+        self
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn order(&self) -> &Vec<[u8; 16]> {
+        &self.order
+    }
+
+    pub fn readied(&self) -> &Vec<Readied> {
+        &self.readied
+    }
+
+    /// Returns the UID of the actor whose turn it is, if a round is in progress
+    pub fn current(&self) -> Option<&[u8; 16]> {
+        self.order.get(self.current)
+    }
+
+    /// Returns the action points remaining to the actor whose turn it is
+    pub fn remaining_points(&self) -> u32 {
+        self.points
+    }
+
+    /// Computes the cells the current actor could reach with its remaining action points, along
+    /// with the action points needed to reach each, charged for each move exactly as by cost()
+    pub fn reachable(&self, world: &World) -> HashMap<coords::Position, u32> {
+        let uid = match self.current() {
+            Some(uid)   => uid,
+            None        => return HashMap::new(),
+        };
+
+        let move_cost = self.move_cost.max(1);
+        movement::reachable(world, uid, self.points / move_cost).into_iter()
+            .map(|(pos, cost)| (pos, cost * move_cost))
+            .collect()
+    }
+
+    /// Determines the action point cost of the given actor taking the given action.
+    /// Moves cost the scheduler's move cost for each movement point spent entering the destination.
+    pub fn cost(&self, world: &World, actor_uid: &[u8; 16], action: &Action) -> Result<u32, TurnError> {
+        match action {
+            Action::Move(dir)   => Ok(self.move_cost * movement::move_cost(world, actor_uid, *dir)?),
+            Action::Cast(_, _)  => Ok(self.cast_cost),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Begins a new round, ordering all undefeated actors by initiative.
+    /// Actors of equal initiative act in the order they were added to the world.
+    pub fn begin_round(&mut self, world: &World) {
+        let mut actors: Vec<(i32, [u8; 16])> = world.actors().iter()
+            .filter(|actor| !actor.is_defeated())
+            .map(|actor| (actor.initiative(), *actor.uid()))
+            .collect();
+        actors.sort_by_key(|&(initiative, _)| std::cmp::Reverse(initiative));
+
+        self.round += 1;
+        self.order = actors.into_iter().map(|(_, uid)| uid).collect();
+        self.current = 0;
+        self.delayed.clear();
+        self.begin_turn(world);
+    }
+
+    /// Performs the given action on behalf of the actor whose turn it is, spending its action points.
+    /// Any readied actions triggered by the action are then taken.
+    pub fn act(&mut self, world: &mut World, action: Action) -> Result<Report, TurnError> {
+        let actor_uid = *self.current().ok_or(TurnError::NoActiveActor)?;
+
+        let cost = self.cost(world, &actor_uid, &action)?;
+        if cost > self.points {
+            return Err(TurnError::InsufficientPoints(cost, self.points));
+        }
+
+        let report = perform(world, &actor_uid, &action)?;
+        self.points -= cost;
+
+        let triggered = self.fire_triggers(world, &actor_uid, &report);
+
+        Ok(Report {action: report, triggered})
+    }
+
+    /// Delays the current actor's turn until the end of the round, banking its remaining points.
+    /// An actor may only delay once per round.
+    pub fn delay(&mut self, world: &World) -> Result<(), TurnError> {
+        let actor_uid = *self.current().ok_or(TurnError::NoActiveActor)?;
+        if self.delayed.contains_key(&actor_uid) {
+            return Err(TurnError::AlreadyDelayed(actor_uid));
+        }
+
+        self.delayed.insert(actor_uid, self.points);
+        self.order.remove(self.current);
+        self.order.push(actor_uid);
+        self.begin_turn(world);
+
+        Ok(())
+    }
+
+    /// Readies the given action to be taken when the trigger occurs, ending the current actor's turn.
+    /// The action's cost is spent immediately. Readied actions lapse at the end of the round.
+    pub fn ready(&mut self, world: &mut World, action: Action, trigger: Trigger) -> Result<(), TurnError> {
+        let actor_uid = *self.current().ok_or(TurnError::NoActiveActor)?;
+
+        let cost = self.cost(world, &actor_uid, &action)?;
+        if cost > self.points {
+            return Err(TurnError::InsufficientPoints(cost, self.points));
+        }
+        self.points -= cost;
+
+        self.readied.push(Readied {actor: actor_uid, action, trigger});
+        self.end_turn(world);
+
+        Ok(())
+    }
+
+    /// Ends the current actor's turn. Returns true if this also ended the round.
+    /// Does nothing, returning false, if no actor's turn is in progress.
+    pub fn end_turn(&mut self, world: &mut World) -> bool {
+        let uid = match self.current() {
+            Some(uid)   => *uid,
+            None        => return false,
+        };
+        if let Some(actor) = world.actor_mut(&uid) {
+            actor.end_turn();
+        }
+
+        self.current += 1;
+        if self.current >= self.order.len() {
+            self.end_round(world);
+            return true;
+        }
+
+        self.begin_turn(world);
+        false
+    }
+
+    /// Ends the current round, advancing the game clock, then begins the next
+    pub fn end_round(&mut self, world: &mut World) {
+        self.readied.clear();
+
+        world.advance(self.round_duration);
+
+        self.begin_round(world);
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Helper Methods    *
+    \*  *  *  *  *  *  *  */
+
+    /// Begins the turn of the actor at the current position in the order, skipping defeated actors
+    fn begin_turn(&mut self, world: &World) {
+        while let Some(uid) = self.order.get(self.current) {
+            match world.actor(uid) {
+                Some(actor) if !actor.is_defeated() => break,
+                _                                   => { self.order.remove(self.current); },
+            }
+        }
+
+        self.points = match self.current() {
+            Some(uid)   => self.delayed.get(uid).cloned().unwrap_or(self.action_points),
+            None        => 0,
+        };
+    }
+
+    /// Takes every readied action triggered by the given actor's action
+    fn fire_triggers(&mut self, world: &mut World, actor_uid: &[u8; 16], report: &ActionReport) -> Vec<Triggered> {
+        let (fired, waiting): (Vec<Readied>, Vec<Readied>) = self.readied.drain(..)
+            .partition(|readied| readied.actor != *actor_uid && is_triggered(world, readied, actor_uid, report));
+        self.readied = waiting;
+
+        fired.into_iter()
+            .map(|readied| {
+                let result = perform(world, &readied.actor, &readied.action);
+                Triggered {readied, result}
+            })
+            .collect()
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Utility Functions
+///////////////////////////////////////////////////////////////////////////////
+
+/// Performs the given action on behalf of the given actor, without regard for action points.
+/// Moves remain subject to the passability of the destination.
+pub fn perform(world: &mut World, actor_uid: &[u8; 16], action: &Action) -> Result<ActionReport, TurnError> {
+    match action {
+        Action::Move(dir)                   => {
+            movement::step(world, actor_uid, *dir, u32::MAX)?;
+            let actor = world.actor(actor_uid).ok_or(TurnError::UnknownActor(*actor_uid))?;
+
+            Ok(ActionReport::Moved(*actor.origin()))
+        },
+        Action::Cast(ability_uid, target)   => {
+            Ok(ActionReport::Cast(cast::execute(world, actor_uid, ability_uid, target)?))
+        },
+    }
+}
+
+/// Determines if the given actor's action triggers the given readied action
+fn is_triggered(world: &World, readied: &Readied, actor_uid: &[u8; 16], report: &ActionReport) -> bool {
+    match readied.trigger {
+        Trigger::Approach(distance) => {
+            let pos = match report {
+                ActionReport::Moved(pos)    => pos,
+                ActionReport::Cast(_)       => return false,
+            };
+
+            match world.actor(&readied.actor) {
+                Some(watcher)   => watcher.origin().distance_to(pos) as usize <= distance,
+                None            => false,
+            }
+        },
+        Trigger::Cast               => matches!(report, ActionReport::Cast(_)),
+        Trigger::ActorActs(uid)     => uid == *actor_uid,
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Trait Implementations
+///////////////////////////////////////////////////////////////////////////////
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/*  *  *  *  *  *  *  *\
+ *     TurnError      *
+\*  *  *  *  *  *  *  */
+impl From<MovementError> for TurnError {
+    fn from(src: MovementError) -> Self {
+        TurnError::Movement(src)
+    }
+}
+impl From<CastError> for TurnError {
+    fn from(src: CastError) -> Self {
+        TurnError::Cast(src)
+    }
+}
+impl Error for TurnError {}
+impl fmt::Display for TurnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TurnError::NoActiveActor                        => {
+                write!(f, "No round is in progress")
+            },
+            TurnError::UnknownActor(uid)                    => {
+                write!(f, "Unknown actor {}", Uuid::from_bytes(*uid))
+            },
+            TurnError::InsufficientPoints(cost, remaining)  => {
+                write!(f, "Action costs {} points, but only {} remain", cost, remaining)
+            },
+            TurnError::AlreadyDelayed(uid)                  => {
+                write!(f, "Actor {} has already delayed this round", Uuid::from_bytes(*uid))
+            },
+            TurnError::Movement(err)                        => {
+                write!(f, "Invalid movement: {}", err)
+            },
+            TurnError::Cast(err)                            => {
+                write!(f, "Cast failed: {}", err)
+            },
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Unit Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        ability::Ability,
+        actor::{
+            Actor,
+            status::{
+                Kind,
+                StatusEffect,
+            },
+        },
+        context::Context,
+        coords::tests::pos,
+        element::{
+            Element,
+            interaction::Matrix,
+        },
+        mechanics::{
+            movement::Terrain,
+            resource::{
+                Resource,
+                State,
+            },
+        },
+    };
+
+    /// Builds a world containing a slow actor and a fast one, returning their UIDs in that order
+    fn setup(ctx: &Context) -> (World, [u8; 16], [u8; 16]) {
+        let mut world = World::new(ctx.clone());
+
+        let slow = Actor::new("Slow", pos(0, 0, 0, ctx), 0, Vec::new());
+        let slow_uid = *slow.uid();
+        world.add_actor(slow);
+
+        let mut fast = Actor::new("Fast", pos(4, 0, -4, ctx), 0, Vec::new());
+        fast.set_initiative(10);
+        let fast_uid = *fast.uid();
+        world.add_actor(fast);
+
+        (world, slow_uid, fast_uid)
+    }
+
+    #[test]
+    fn initiative_and_points() {
+        let ctx = Context::default();
+        let (mut world, slow_uid, fast_uid) = setup(&ctx);
+        world.add_resource(Resource::new(crate::element::Element::Fire, State::Low, pos(0, 0, 0, &ctx), 1));
+
+        // No turn is in progress until the first round begins
+        let mut scheduler = Scheduler::new().with_action_points(2);
+        assert!(!scheduler.end_turn(&mut world));
+        assert_eq!(scheduler.round(), 0);
+        assert_eq!(world.tick(), 0.0);
+
+        scheduler.begin_round(&world);
+        assert_eq!(scheduler.order(), &vec![fast_uid, slow_uid]);
+        assert_eq!(scheduler.reachable(&world).len(), 19);
+
+        scheduler.act(&mut world, Action::Move(hex_directions::Side::South)).unwrap();
+        scheduler.act(&mut world, Action::Move(hex_directions::Side::South)).unwrap();
+        match scheduler.act(&mut world, Action::Move(hex_directions::Side::South)) {
+            Err(TurnError::InsufficientPoints(1, 0)) => {},
+            other => panic!("Expected insufficient points, got {:?}", other),
+        }
+
+        // Ending the last turn ends the round, advancing the clock and regrowing resources
+        assert!(!scheduler.end_turn(&mut world));
+        assert_eq!(scheduler.current(), Some(&slow_uid));
+        assert!(scheduler.end_turn(&mut world));
+        assert_eq!(scheduler.round(), 2);
+        assert_eq!(world.tick(), DEFAULT_ROUND_SECS as f64);
+        assert_eq!(world.resources()[0].state() as u8, State::Partial as u8);
+    }
+
+    #[test]
+    fn reachable_matches_cost() {
+        let ctx = Context::default();
+        let (mut world, _slow_uid, fast_uid) = setup(&ctx);
+        world.set_terrain(Terrain::new(2));
+
+        let mut scheduler = Scheduler::new().with_action_points(4).with_costs(2, 1);
+        scheduler.begin_round(&world);
+        assert_eq!(scheduler.current(), Some(&fast_uid));
+
+        // Each reachable cell is priced as the move into it would be charged
+        let north = pos(4, 1, -5, &ctx);
+        let move_north = Action::Move(hex_directions::Side::North);
+        let reach = scheduler.reachable(&world);
+        assert_eq!(reach.len(), 7);
+        assert_eq!(reach.get(&north), Some(&scheduler.cost(&world, &fast_uid, &move_north).unwrap()));
+
+        // Tired actors reach less far, and hastened actors further
+        world.actor_mut(&fast_uid).unwrap().exert(130).unwrap();
+        assert_eq!(scheduler.cost(&world, &fast_uid, &move_north).unwrap(), 6);
+        assert_eq!(scheduler.reachable(&world).len(), 1);
+        world.actor_mut(&fast_uid).unwrap().recover_fatigue(Duration::from_secs(600));
+
+        let haste = StatusEffect::new(Kind::Hastened, Element::Wind, fast_uid, 0.0, 10.0, 1.0);
+        world.actor_mut(&fast_uid).unwrap().apply_status(haste, &Matrix::default(), 0.0);
+        let reach = scheduler.reachable(&world);
+        assert_eq!(reach.len(), 19);
+        assert_eq!(reach.get(&north), Some(&scheduler.cost(&world, &fast_uid, &move_north).unwrap()));
+
+        // Collapsed actors cannot move anywhere
+        world.actor_mut(&fast_uid).unwrap().exert(255).unwrap();
+        assert!(scheduler.reachable(&world).is_empty());
+    }
+
+    #[test]
+    fn delay_and_ready() {
+        let ctx = Context::default();
+        let (mut world, slow_uid, fast_uid) = setup(&ctx);
+
+        let zap = Ability::new_name_only("Zap").with_range(2);
+        let zap_uid = *zap.uid();
+        world.actor_mut(&slow_uid).unwrap().add_ability(zap);
+
+        let mut scheduler = Scheduler::new();
+        scheduler.begin_round(&world);
+
+        // Fast delays, letting Slow act first
+        scheduler.delay(&world).unwrap();
+        assert_eq!(scheduler.current(), Some(&slow_uid));
+
+        // Slow readies a cast for when anything comes near
+        scheduler.ready(&mut world, Action::Cast(zap_uid, Target::Actor(fast_uid)), Trigger::Approach(2)).unwrap();
+        assert_eq!(scheduler.current(), Some(&fast_uid));
+        assert_eq!(scheduler.remaining_points(), DEFAULT_ACTION_POINTS);
+        match scheduler.delay(&world) {
+            Err(TurnError::AlreadyDelayed(_)) => {},
+            other => panic!("Expected already delayed, got {:?}", other),
+        }
+
+        let report = scheduler.act(&mut world, Action::Move(hex_directions::Side::SouthWest)).unwrap();
+        assert!(report.triggered.is_empty());
+        let report = scheduler.act(&mut world, Action::Move(hex_directions::Side::SouthWest)).unwrap();
+        assert_eq!(report.triggered.len(), 1);
+        match &report.triggered[0].result {
+            Ok(ActionReport::Cast(outcome)) => assert_eq!(outcome.caster, slow_uid),
+            other => panic!("Expected readied cast, got {:?}", other),
+        }
+        assert!(scheduler.readied().is_empty());
+    }
+}
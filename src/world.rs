@@ -0,0 +1,543 @@
+/* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *\
+Filename : world.rs
+
+Copyright (C) 2020 CJ McAllister
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or
+    (at your option) any later version.
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software Foundation,
+    Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301  USA
+
+Purpose:
+    This module defines the World object, which owns the actors and mechanics
+    of a game world along with the game clock that drives them.
+
+    Systems that need to see or mutate more than one object at a time (e.g.
+    ability execution) operate on a World.
+
+\* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * */
+
+use std::time::Duration;
+
+use crate::{
+    ability::{
+        cast::{
+            self,
+            Outcome,
+            PendingCast,
+        },
+        modifier::RuleTable,
+    },
+    actor::{
+        Actor,
+        combo::ComboBook,
+        faction::Relations,
+    },
+    context::Context,
+    coords,
+    element::interaction,
+    hex_directions,
+    mechanics::{
+        control::{
+            self,
+            ControlEvent,
+        },
+        movement::{
+            Collision,
+            Terrain,
+        },
+        network::{
+            self,
+            Network,
+        },
+        obstacle::{
+            self,
+            Obstacle,
+        },
+        reaction::{
+            self,
+            Mutation,
+        },
+        resource::Resource,
+        weather,
+    },
+    Plottable,
+};
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Data Structures
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+pub struct World {
+    ctx:            Context,
+    tick:           f64,                    // Current game time (in seconds)
+    actors:         Vec<Actor>,
+    obstacles:      Vec<Obstacle>,
+    resources:      Vec<Resource>,
+    terrain:        Terrain,                // Movement costs of the cells of this world
+    collision:      Collision,              // Resolution of moves into occupied cells
+    weather:        weather::Event,
+    interactions:   interaction::Matrix,    // Elemental interactions in effect for this world
+    aspect_rules:   RuleTable,              // Aspect rules in effect for this world
+    combos:         ComboBook,              // Combos recognised in this world
+    relations:      Relations,              // Relationships between the factions of this world
+    control_events: Vec<ControlEvent>,      // Changes of resource control not yet taken
+    network:        Network,                // Ley lines linking the resources of this world
+    burn_carry:     f64,                    // Game time carried towards the next spread of fire (in seconds)
+    gust_carry:     f64,                    // Game time carried towards the next gust of weather (in seconds)
+    pending_casts:  Vec<PendingCast>,       // Casts whose effects await the end of their cast time
+    cast_outcomes:  Vec<Outcome>,           // Outcomes of pending casts completed but not yet taken
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+//  Object Implementation
+///////////////////////////////////////////////////////////////////////////////
+
+impl World {
+    /// Constructs an empty world governed by the given Context
+    pub fn new(ctx: Context) -> Self {
+        Self {
+            ctx,
+            tick:           0.0,
+            actors:         Vec::new(),
+            obstacles:      Vec::new(),
+            resources:      Vec::new(),
+            terrain:        Terrain::default(),
+            collision:      Collision::default(),
+            weather:        weather::Event::default(),
+            interactions:   interaction::Matrix::default(),
+            aspect_rules:   RuleTable::default(),
+            combos:         ComboBook::default(),
+            relations:      Relations::default(),
+            control_events: Vec::new(),
+            network:        Network::default(),
+            burn_carry:     0.0,
+            gust_carry:     0.0,
+            pending_casts:  Vec::new(),
+            cast_outcomes:  Vec::new(),
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Accessor Methods  *
+    \*  *  *  *  *  *  *  */
+
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+
+    pub fn tick(&self) -> f64 {
+        self.tick
+    }
+
+    pub fn actors(&self) -> &Vec<Actor> {
+        &self.actors
+    }
+
+    pub fn obstacles(&self) -> &Vec<Obstacle> {
+        &self.obstacles
+    }
+
+    pub fn resources(&self) -> &Vec<Resource> {
+        &self.resources
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Returns the changes of resource control recorded since they were last taken
+    pub fn control_events(&self) -> &Vec<ControlEvent> {
+        &self.control_events
+    }
+
+    pub fn pending_casts(&self) -> &Vec<PendingCast> {
+        &self.pending_casts
+    }
+
+    /// Returns the outcomes of pending casts completed since they were last taken
+    pub fn cast_outcomes(&self) -> &Vec<Outcome> {
+        &self.cast_outcomes
+    }
+
+    pub fn terrain(&self) -> &Terrain {
+        &self.terrain
+    }
+
+    pub fn terrain_mut(&mut self) -> &mut Terrain {
+        &mut self.terrain
+    }
+
+    pub fn collision(&self) -> Collision {
+        self.collision
+    }
+
+    pub fn weather(&self) -> &weather::Event {
+        &self.weather
+    }
+
+    pub fn interactions(&self) -> &interaction::Matrix {
+        &self.interactions
+    }
+
+    pub fn aspect_rules(&self) -> &RuleTable {
+        &self.aspect_rules
+    }
+
+    pub fn combos(&self) -> &ComboBook {
+        &self.combos
+    }
+
+    pub fn relations(&self) -> &Relations {
+        &self.relations
+    }
+
+    /// Returns a reference to the actor with the given UID, if present
+    pub fn actor(&self, uid: &[u8; 16]) -> Option<&Actor> {
+        self.actors.iter().find(|actor| actor.uid() == uid)
+    }
+
+    /// Returns a mutable reference to the actor with the given UID, if present
+    pub fn actor_mut(&mut self, uid: &[u8; 16]) -> Option<&mut Actor> {
+        self.actors.iter_mut().find(|actor| actor.uid() == uid)
+    }
+
+    /// Returns a reference to the actor standing at the given position, if any
+    pub fn actor_at(&self, pos: &coords::Position) -> Option<&Actor> {
+        self.actors.iter().find(|actor| actor.origin() == pos)
+    }
+
+    /// Returns a reference to the obstacle with the given UID, if present
+    pub fn obstacle(&self, uid: &[u8; 16]) -> Option<&Obstacle> {
+        self.obstacles.iter().find(|obstacle| obstacle.uid() == uid)
+    }
+
+    /// Returns a reference to the resource with the given UID, if present
+    pub fn resource(&self, uid: &[u8; 16]) -> Option<&Resource> {
+        self.resources.iter().find(|resource| resource.uid() == uid)
+    }
+
+    /// Returns a mutable reference to the resource with the given UID, if present
+    pub fn resource_mut(&mut self, uid: &[u8; 16]) -> Option<&mut Resource> {
+        self.resources.iter_mut().find(|resource| resource.uid() == uid)
+    }
+
+    /// Returns all resources whose radius covers the given position
+    pub fn resources_covering(&self, pos: &coords::Position) -> Vec<&Resource> {
+        self.resources.iter().filter(|resource| resource.covers(pos)).collect()
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Mutator Methods   *
+    \*  *  *  *  *  *  *  */
+
+    pub fn add_actor(&mut self, actor: Actor) {
+        self.actors.push(actor);
+    }
+
+    /// Adds the given obstacle, cutting any ley lines running through it
+    pub fn add_obstacle(&mut self, obstacle: Obstacle) {
+        self.obstacles.push(obstacle);
+        self.relink();
+    }
+
+    /// Adds the given resource, linking it to the network
+    pub fn add_resource(&mut self, resource: Resource) {
+        let neighbours = network::neighbours(self, &resource, self.network.link_distance());
+        self.network.insert(resource.uid(), &neighbours);
+        self.resources.push(resource);
+    }
+
+    /// Removes and returns the resource with the given UID, if present, unlinking it from the network
+    pub fn remove_resource(&mut self, uid: &[u8; 16]) -> Option<Resource> {
+        let index = self.resources.iter().position(|resource| resource.uid() == uid)?;
+        self.network.remove(uid);
+
+        Some(self.resources.remove(index))
+    }
+
+    /// Moves the resource with the given UID to the given position, relinking it to the network.
+    /// Returns false if no such resource is present.
+    pub fn move_resource(&mut self, uid: &[u8; 16], origin: coords::Position) -> bool {
+        let resource = match self.resources.iter_mut().find(|resource| resource.uid() == uid) {
+            Some(resource)  => resource,
+            None            => return false,
+        };
+        resource.set_origin(origin);
+
+        let resource = self.resource(uid).unwrap();
+        let neighbours = network::neighbours(self, resource, self.network.link_distance());
+        self.network.insert(uid, &neighbours);
+
+        true
+    }
+
+    /// Draws upon the resource with the given UID. The drain falls upon the fullest resource
+    /// of its network, favouring the given resource itself.
+    /// Returns the UID of the resource drained and the magnitude drawn, or None if the network is depleted.
+    pub fn consume_resource(&mut self, uid: &[u8; 16]) -> Option<([u8; 16], u8)> {
+        let drained = self.network.members(uid).into_iter()
+            .filter_map(|member| self.resource(&member))
+            .fold(None, |fullest: Option<&Resource>, resource| match fullest {
+                Some(fullest) if fullest.state() >= resource.state() => Some(fullest),
+                _                                                   => Some(resource),
+            })
+            .map(|resource| *resource.uid())?;
+
+        let magnitude = self.resource_mut(&drained)?.consume()?;
+        Some((drained, magnitude))
+    }
+
+    /// Replenishes the resource with the given UID by the given magnitude. Each step of the
+    /// replenishment falls upon the emptiest resource of its network, favouring the given
+    /// resource itself.
+    pub fn replenish_resource(&mut self, uid: &[u8; 16], mag: u8) {
+        let members = self.network.members(uid);
+
+        for _ in 0 .. mag {
+            let filled = members.iter()
+                .filter_map(|member| self.resource(member))
+                .fold(None, |emptiest: Option<&Resource>, resource| match emptiest {
+                    Some(emptiest) if emptiest.state() <= resource.state() => Some(emptiest),
+                    _                                                     => Some(resource),
+                })
+                .map(|resource| *resource.uid());
+
+            match filled.and_then(|filled| self.resource_mut(&filled)) {
+                Some(resource)  => resource.replenish(1),
+                None            => return,
+            }
+        }
+    }
+
+    pub fn set_terrain(&mut self, terrain: Terrain) {
+        self.terrain = terrain;
+    }
+
+    pub fn set_collision(&mut self, collision: Collision) {
+        self.collision = collision;
+    }
+
+    pub fn set_weather(&mut self, weather: weather::Event) {
+        self.weather = weather;
+    }
+
+    pub fn set_interactions(&mut self, interactions: interaction::Matrix) {
+        self.interactions = interactions;
+    }
+
+    pub fn set_aspect_rules(&mut self, aspect_rules: RuleTable) {
+        self.aspect_rules = aspect_rules;
+    }
+
+    pub fn set_combos(&mut self, combos: ComboBook) {
+        self.combos = combos;
+    }
+
+    pub fn set_relations(&mut self, relations: Relations) {
+        self.relations = relations;
+    }
+
+    /// Sets the maximum distance between linked resources, relinking the network
+    pub fn set_link_distance(&mut self, link_distance: usize) {
+        self.network.set_link_distance(link_distance);
+        self.relink();
+    }
+
+    /// Rebuilds every ley line of the network from the current resources and obstacles
+    pub fn relink(&mut self) {
+        let mut network = std::mem::take(&mut self.network);
+        network.rebuild(self);
+        self.network = network;
+    }
+
+    /// Removes and returns the changes of resource control recorded so far
+    pub fn take_control_events(&mut self) -> Vec<ControlEvent> {
+        std::mem::take(&mut self.control_events)
+    }
+
+    /// Queues the given cast to take effect once its cast time has elapsed
+    pub fn queue_cast(&mut self, pending: PendingCast) {
+        self.pending_casts.push(pending);
+    }
+
+    /// Removes and returns the outcomes of pending casts completed so far
+    pub fn take_cast_outcomes(&mut self) -> Vec<Outcome> {
+        std::mem::take(&mut self.cast_outcomes)
+    }
+
+    /// Applies the given mutation to the world.
+    /// Mutations referring to objects no longer present in the world are ignored.
+    pub fn apply_mutation(&mut self, mutation: &Mutation) {
+        match mutation {
+            Mutation::RemoveObstacleCells(uid, positions)           => {
+                self.reshape_obstacle(uid, |obstacle| obstacle.remove_positions(positions));
+            },
+            Mutation::DamageObstacleCells(uid, positions, amount)   => {
+                self.reshape_obstacle(uid, |obstacle| {
+                    obstacle.damage(positions, *amount);
+                });
+            },
+            Mutation::IgniteObstacleCells(uid, positions)           => {
+                if let Some(obstacle) = self.obstacles.iter_mut().find(|obstacle| obstacle.uid() == uid) {
+                    obstacle.ignite(positions);
+                }
+            },
+            Mutation::ExtinguishObstacleCells(uid, positions)       => {
+                if let Some(obstacle) = self.obstacles.iter_mut().find(|obstacle| obstacle.uid() == uid) {
+                    obstacle.extinguish(positions);
+                }
+            },
+            Mutation::PushObstacle(uid, dir)                        => {
+                // Obstacles pushed against the edge of the grid, an actor or another obstacle simply stay put
+                if self.can_push_obstacle(uid, *dir) {
+                    let trans = coords::Translation::from(*dir);
+                    if let Some(obstacle) = self.obstacles.iter_mut().find(|obstacle| obstacle.uid() == uid) {
+                        let _res = obstacle.translate(&trans, &self.ctx);
+                    }
+                    self.relink();
+                }
+            },
+            Mutation::ConsumeResource(uid)                          => {
+                self.consume_resource(uid);
+            },
+            Mutation::ReplenishResource(uid, mag)                   => {
+                self.replenish_resource(uid, *mag);
+            },
+        }
+    }
+
+    /// Advances the game clock by the given amount of time, updating all time-driven state
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.tick += elapsed.as_secs_f64();
+
+        for actor in self.actors.iter_mut() {
+            actor.recover_fatigue(elapsed);
+            actor.update_statuses(self.tick, elapsed);
+        }
+
+        // Casts whose cast time has elapsed take effect, unless their casters were interrupted
+        let tick = self.tick;
+        let (due, pending): (Vec<PendingCast>, Vec<PendingCast>) = std::mem::take(&mut self.pending_casts)
+            .into_iter()
+            .partition(|pending| pending.completes_at() <= tick);
+        self.pending_casts = pending;
+        for pending in due {
+            if let Some(outcome) = cast::complete(self, pending) {
+                self.cast_outcomes.push(outcome);
+            }
+        }
+
+        let claimants: Vec<_> = self.resources.iter()
+            .map(|resource| control::claimants(self, resource))
+            .collect();
+        for (resource, claimants) in self.resources.iter_mut().zip(claimants) {
+            let previous = resource.controller().cloned();
+            if resource.update_control(&claimants, elapsed) {
+                if let Some(controller) = resource.controller() {
+                    self.control_events.push(ControlEvent {
+                        resource:   *resource.uid(),
+                        previous,
+                        controller: controller.clone(),
+                        tick:       self.tick,
+                    });
+                }
+            }
+
+            resource.update(elapsed);
+        }
+
+        // Fire spreads among the obstacles at regular intervals
+        let (steps, carry) = obstacle::burn_steps(elapsed.as_secs_f64(), self.burn_carry);
+        self.burn_carry = carry;
+        for _ in 0 .. steps {
+            for mutation in obstacle::evaluate_burn(self) {
+                self.apply_mutation(&mutation);
+            }
+        }
+
+        // Strong weather gusts at regular intervals, scattering obstacles before it
+        let (steps, carry) = weather::gust_steps(elapsed.as_secs_f64(), self.gust_carry);
+        self.gust_carry = carry;
+        for _ in 0 .. steps {
+            for mutation in reaction::evaluate_weather(self) {
+                self.apply_mutation(&mutation);
+            }
+        }
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Utility Methods   *
+    \*  *  *  *  *  *  *  */
+
+    /// Determines if the given position is occupied by an actor
+    pub fn is_occupied(&self, pos: &coords::Position) -> bool {
+        self.actor_at(pos).is_some()
+    }
+
+    /// Determines if the given position is occupied by an obstacle
+    pub fn is_obstructed(&self, pos: &coords::Position) -> bool {
+        self.obstacles.iter().any(|obstacle| obstacle.contains(pos))
+    }
+
+    /// Determines if the obstacle with the given UID may be pushed one cell in the given direction.
+    /// Every cell must land within the grid, on a cell free of actors and of any other obstacle.
+    pub fn can_push_obstacle(&self, uid: &[u8; 16], dir: hex_directions::Side) -> bool {
+        let obstacle = match self.obstacle(uid) {
+            Some(obstacle)  => obstacle,
+            None            => return false,
+        };
+
+        let trans = coords::Translation::from(dir);
+        obstacle.positions().iter().all(|pos| {
+            let mut dest = *pos;
+            dest.translate(&trans, &self.ctx).is_ok() &&
+            !self.is_occupied(&dest) &&
+            !self.obstacles.iter().any(|other| other.uid() != uid && other.contains(&dest))
+        })
+    }
+
+    /// Determines if there is an unobstructed line of sight between the two positions.
+    /// The endpoints themselves are not considered.
+    pub fn has_line_of_sight(&self, from: &coords::Position, to: &coords::Position) -> bool {
+        let line = from.line_to(to);
+
+        line.iter()
+            .skip(1)
+            .take(line.len().saturating_sub(2))
+            .all(|pos| !self.is_obstructed(pos))
+    }
+
+
+    /*  *  *  *  *  *  *  *\
+     *  Helper Methods    *
+    \*  *  *  *  *  *  *  */
+
+    /// Reshapes the obstacle with the given UID, splitting it into contiguous pieces afterwards
+    /// and relinking the network around it
+    fn reshape_obstacle<F: FnOnce(&mut Obstacle)>(&mut self, uid: &[u8; 16], reshape: F) {
+        let index = match self.obstacles.iter().position(|obstacle| obstacle.uid() == uid) {
+            Some(index) => index,
+            None        => return,
+        };
+
+        let mut obstacle = self.obstacles.remove(index);
+        reshape(&mut obstacle);
+        let pieces = obstacle.split();
+        self.obstacles.splice(index .. index, pieces);
+
+        self.relink();
+    }
+}